@@ -0,0 +1,27 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+#extension GL_ARB_shading_language_420pack : enable
+
+layout (binding = 0) uniform sampler2D source;
+layout (binding = 1) uniform sampler2D extra;
+
+layout (push_constant) uniform PushConstants {
+    vec2 texel_size;
+    float time;
+    float padding;
+    vec4 params[4];
+} pc;
+
+layout (location = 0) in vec2 o_uv;
+layout (location = 0) out vec4 u_frag_color;
+
+// params[0]: threshold
+// Keeps only what's brighter than the threshold, drawn into a target half the size.
+void main() {
+    vec3 color = texture(source, o_uv).rgb;
+
+    float brightness = max(color.r, max(color.g, color.b));
+    float contribution = max(brightness - pc.params[0].x, 0.0) / max(brightness, 0.0001);
+
+    u_frag_color = vec4(color * contribution, 1.0);
+}
@@ -0,0 +1,30 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+#extension GL_ARB_shading_language_420pack : enable
+
+layout (binding = 0) uniform sampler2D source;
+layout (binding = 1) uniform sampler2D extra;
+
+layout (push_constant) uniform PushConstants {
+    vec2 texel_size;
+    float time;
+    float padding;
+    vec4 params[4];
+} pc;
+
+layout (location = 0) in vec2 o_uv;
+layout (location = 0) out vec4 u_frag_color;
+
+// params[0]: direction, scaled by the radius in texels
+// A 9 tap gaussian blur in 5 samples, relying on linear filtering to blend pairs of taps.
+void main() {
+    vec2 offset = pc.params[0].xy * pc.texel_size;
+
+    vec3 color = texture(source, o_uv).rgb * 0.2270270270;
+    color += texture(source, o_uv + offset * 1.3846153846).rgb * 0.3162162162;
+    color += texture(source, o_uv - offset * 1.3846153846).rgb * 0.3162162162;
+    color += texture(source, o_uv + offset * 3.2307692308).rgb * 0.0702702703;
+    color += texture(source, o_uv - offset * 3.2307692308).rgb * 0.0702702703;
+
+    u_frag_color = vec4(color, 1.0);
+}
@@ -0,0 +1,10 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+#extension GL_ARB_shading_language_420pack : enable
+
+layout (location = 0) in vec4 o_color;
+layout (location = 0) out vec4 u_frag_color;
+
+void main() {
+    u_frag_color = o_color;
+}
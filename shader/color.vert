@@ -0,0 +1,17 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+#extension GL_ARB_shading_language_420pack : enable
+
+layout (binding = 0) uniform UBO {
+    mat4 projection_matrix;
+} ubo;
+
+layout (location = 0) in vec3 i_pos;
+layout (location = 1) in vec4 i_color;
+
+
+layout (location = 0) out vec4 o_color;
+void main() {
+    o_color = i_color;
+    gl_Position = ubo.projection_matrix * vec4(i_pos, 1.0);
+}
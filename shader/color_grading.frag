@@ -0,0 +1,37 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+#extension GL_ARB_shading_language_420pack : enable
+
+layout (binding = 0) uniform sampler2D source;
+layout (binding = 1) uniform sampler2D extra;
+
+layout (push_constant) uniform PushConstants {
+    vec2 texel_size;
+    float time;
+    float padding;
+    vec4 params[4];
+} pc;
+
+layout (location = 0) in vec2 o_uv;
+layout (location = 0) out vec4 u_frag_color;
+
+// params[0]: lut size, intensity
+// The LUT is a strip of squares, red increasing across each square, green down it and blue
+// from one square to the next.
+void main() {
+    vec4 color = texture(source, o_uv);
+    float size = pc.params[0].x;
+
+    vec3 cell = clamp(color.rgb, 0.0, 1.0) * (size - 1.0);
+    float slice = floor(cell.b);
+    float next_slice = min(slice + 1.0, size - 1.0);
+
+    float v = (cell.g + 0.5) / size;
+    vec3 graded = mix(
+        texture(extra, vec2((slice * size + cell.r + 0.5) / (size * size), v)).rgb,
+        texture(extra, vec2((next_slice * size + cell.r + 0.5) / (size * size), v)).rgb,
+        cell.b - slice
+    );
+
+    u_frag_color = vec4(mix(color.rgb, graded, pc.params[0].y), color.a);
+}
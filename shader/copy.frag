@@ -0,0 +1,20 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+#extension GL_ARB_shading_language_420pack : enable
+
+layout (binding = 0) uniform sampler2D source;
+layout (binding = 1) uniform sampler2D extra;
+
+layout (push_constant) uniform PushConstants {
+    vec2 texel_size;
+    float time;
+    float padding;
+    vec4 params[4];
+} pc;
+
+layout (location = 0) in vec2 o_uv;
+layout (location = 0) out vec4 u_frag_color;
+
+void main() {
+    u_frag_color = texture(source, o_uv);
+}
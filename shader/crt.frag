@@ -0,0 +1,37 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+#extension GL_ARB_shading_language_420pack : enable
+
+layout (binding = 0) uniform sampler2D source;
+layout (binding = 1) uniform sampler2D extra;
+
+layout (push_constant) uniform PushConstants {
+    vec2 texel_size;
+    float time;
+    float padding;
+    vec4 params[4];
+} pc;
+
+layout (location = 0) in vec2 o_uv;
+layout (location = 0) out vec4 u_frag_color;
+
+// params[0]: curvature, scanline intensity
+void main() {
+    // Bulge the image outwards like the glass of a CRT.
+    vec2 centered = o_uv * 2.0 - 1.0;
+    centered += centered * (centered.yx * centered.yx) * pc.params[0].x;
+    vec2 uv = centered * 0.5 + 0.5;
+
+    if (uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+        u_frag_color = vec4(0.0, 0.0, 0.0, 1.0);
+        return;
+    }
+
+    vec4 color = texture(source, uv);
+
+    // Brightest through the middle of each of the source's rows, dark between them.
+    float scanline = abs(sin(uv.y / pc.texel_size.y * 3.14159265));
+    color.rgb *= mix(1.0, scanline, pc.params[0].y);
+
+    u_frag_color = color;
+}
@@ -0,0 +1,33 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+#extension GL_ARB_shading_language_420pack : enable
+
+// params[0].x is how dissolved the sprite is, from 0 to 1, and params[1] the edge's color.
+layout (push_constant) uniform Material {
+    vec4 params[4];
+} material;
+
+layout (binding = 1) uniform sampler2D sampler_color;
+
+layout (location = 0) in vec2 o_uv;
+layout (location = 0) out vec4 u_frag_color;
+
+float noise(vec2 uv) {
+    return fract(sin(dot(floor(uv * 64.0), vec2(12.9898, 78.233))) * 43758.5453);
+}
+
+void main() {
+    vec4 color = texture(sampler_color, o_uv);
+    float threshold = material.params[0].x;
+    float value = noise(o_uv);
+
+    if (color.a < 1.0 || value < threshold) {
+        discard;
+    }
+
+    if (threshold > 0.0 && value < threshold + 0.05) {
+        color = material.params[1];
+    }
+
+    u_frag_color = color;
+}
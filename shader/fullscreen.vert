@@ -0,0 +1,12 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+#extension GL_ARB_shading_language_420pack : enable
+
+layout (location = 0) out vec2 o_uv;
+void main() {
+    // One triangle covering the whole target, with uvs from 0 to 2 so the target spans 0 to 1.
+    uint index = uint(gl_VertexIndex);
+    o_uv = vec2(float((index << 1u) & 2u), float(index & 2u));
+    // As far back as possible, so anything drawn after it is in front.
+    gl_Position = vec4(o_uv * 2.0 - 1.0, 1.0, 1.0);
+}
\ No newline at end of file
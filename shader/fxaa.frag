@@ -0,0 +1,63 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+#extension GL_ARB_shading_language_420pack : enable
+
+layout (binding = 0) uniform sampler2D source;
+layout (binding = 1) uniform sampler2D extra;
+
+layout (push_constant) uniform PushConstants {
+    vec2 texel_size;
+    float time;
+    float padding;
+    vec4 params[4];
+} pc;
+
+layout (location = 0) in vec2 o_uv;
+layout (location = 0) out vec4 u_frag_color;
+
+const float SPAN_MAX = 8.0;
+const float REDUCE_MUL = 1.0 / 8.0;
+const float REDUCE_MIN = 1.0 / 128.0;
+const vec3 LUMA = vec3(0.299, 0.587, 0.114);
+
+// Blurs along edges found from the luma of neighbouring pixels.
+void main() {
+    vec2 texel = pc.texel_size;
+
+    vec4 color = texture(source, o_uv);
+    float luma_nw = dot(texture(source, o_uv + vec2(-1.0, -1.0) * texel).rgb, LUMA);
+    float luma_ne = dot(texture(source, o_uv + vec2(1.0, -1.0) * texel).rgb, LUMA);
+    float luma_sw = dot(texture(source, o_uv + vec2(-1.0, 1.0) * texel).rgb, LUMA);
+    float luma_se = dot(texture(source, o_uv + vec2(1.0, 1.0) * texel).rgb, LUMA);
+    float luma_m = dot(color.rgb, LUMA);
+
+    float luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    float luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    vec2 direction = vec2(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se)
+    );
+    float direction_reduce = max(
+        (luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * REDUCE_MUL,
+        REDUCE_MIN
+    );
+    float inverse_direction_min = 1.0 / (min(abs(direction.x), abs(direction.y)) + direction_reduce);
+    direction = clamp(direction * inverse_direction_min, vec2(-SPAN_MAX), vec2(SPAN_MAX)) * texel;
+
+    vec3 color_a = 0.5 * (
+        texture(source, o_uv + direction * (1.0 / 3.0 - 0.5)).rgb +
+        texture(source, o_uv + direction * (2.0 / 3.0 - 0.5)).rgb
+    );
+    vec3 color_b = color_a * 0.5 + 0.25 * (
+        texture(source, o_uv + direction * -0.5).rgb +
+        texture(source, o_uv + direction * 0.5).rgb
+    );
+    float luma_b = dot(color_b, LUMA);
+
+    if (luma_b < luma_min || luma_b > luma_max) {
+        u_frag_color = vec4(color_a, color.a);
+    } else {
+        u_frag_color = vec4(color_b, color.a);
+    }
+}
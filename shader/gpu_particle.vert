@@ -0,0 +1,32 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+#extension GL_ARB_shading_language_420pack : enable
+
+layout (binding = 2) uniform UBO {
+    mat4 projection_matrix;
+} ubo;
+
+layout (push_constant) uniform PushConstants {
+    vec4 region;
+    float z;
+} pc;
+
+layout (location = 0) in vec4 i_color;
+layout (location = 1) in vec2 i_position;
+layout (location = 2) in float i_size;
+
+
+layout (location = 0) out vec2 o_uv;
+layout (location = 1) out vec4 o_color;
+void main() {
+    // Each instance is a quad of two triangles, with corners in the same order as sprites.
+    uint index = uint(gl_VertexIndex);
+    vec2 corner = vec2(
+        (index == 2u || index == 3u || index == 4u) ? 1.0 : 0.0,
+        (index == 1u || index == 2u || index == 3u) ? 1.0 : 0.0
+    );
+
+    o_uv = pc.region.xy + corner * pc.region.zw;
+    o_color = i_color;
+    gl_Position = ubo.projection_matrix * vec4(i_position + (corner - 0.5) * i_size, pc.z, 1.0);
+}
\ No newline at end of file
@@ -0,0 +1,20 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+#extension GL_ARB_shading_language_420pack : enable
+
+layout (binding = 2) uniform UBO {
+    mat4 projection_matrix;
+} ubo;
+
+layout (location = 0) in vec3 i_pos;
+layout (location = 1) in vec2 i_uv;
+layout (location = 2) in vec4 i_color;
+
+
+layout (location = 0) out vec2 o_uv;
+layout (location = 1) out vec4 o_color;
+void main() {
+    o_uv = i_uv;
+    o_color = i_color;
+    gl_Position = ubo.projection_matrix * vec4(i_pos, 1.0);
+}
\ No newline at end of file
@@ -0,0 +1,21 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+#extension GL_ARB_shading_language_420pack : enable
+
+layout (binding = 2) uniform UBO {
+    mat4 projection_matrix;
+} ubo;
+
+layout (push_constant) uniform PushConstants {
+    vec2 camera;
+} push_constants;
+
+layout (location = 0) in vec3 i_pos;
+layout (location = 1) in vec2 i_uv;
+
+
+layout (location = 0) out vec2 o_uv;
+void main() {
+    o_uv = i_uv;
+    gl_Position = ubo.projection_matrix * vec4(i_pos.xy - push_constants.camera, i_pos.z, 1.0);
+}
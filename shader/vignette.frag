@@ -0,0 +1,27 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+#extension GL_ARB_shading_language_420pack : enable
+
+layout (binding = 0) uniform sampler2D source;
+layout (binding = 1) uniform sampler2D extra;
+
+layout (push_constant) uniform PushConstants {
+    vec2 texel_size;
+    float time;
+    float padding;
+    vec4 params[4];
+} pc;
+
+layout (location = 0) in vec2 o_uv;
+layout (location = 0) out vec4 u_frag_color;
+
+// params[0]: intensity, radius, softness
+void main() {
+    vec4 color = texture(source, o_uv);
+
+    // 0 at the center, 1 in the corners.
+    float distance = length(o_uv - 0.5) * 1.41421356;
+    float vignette = smoothstep(pc.params[0].y, pc.params[0].y + pc.params[0].z, distance);
+
+    u_frag_color = vec4(color.rgb * (1.0 - vignette * pc.params[0].x), color.a);
+}
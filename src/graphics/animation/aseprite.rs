@@ -0,0 +1,218 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    rc,
+};
+
+use serde_json::Value;
+
+use super::{Animation, Frame, PlayMode};
+use crate::graphics::{sprite_batch::Region, vec2::Vec2};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SliceKey {
+    // First frame this key applies to, it lasts until the next key.
+    pub frame: usize,
+    // Bounds within the untrimmed frame, in pixels.
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    // Nine-patch center as x, y, width and height relative to the bounds.
+    pub center: Option<[f32; 4]>,
+    pub pivot: Option<Vec2>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Slice {
+    pub keys: Vec<SliceKey>,
+}
+
+impl Slice {
+    pub fn key(&self, frame: usize) -> Option<&SliceKey> {
+        self.keys.iter().rev().find(|key| key.frame <= frame)
+    }
+}
+
+// A sprite sheet exported from Aseprite as JSON, in either the hash or array layout.
+pub struct SpriteSheet {
+    pub image: Option<PathBuf>,
+    pub width: f32,
+    pub height: f32,
+    pub frames: Vec<Frame>,
+    pub animations: HashMap<String, rc::Rc<Animation>>,
+    pub slices: HashMap<String, Slice>,
+}
+
+impl SpriteSheet {
+    pub fn load(path: &str) -> Self {
+        let data =
+            fs::read_to_string(path).unwrap_or_else(|_| panic!("Failed to load file {}", path));
+        let directory = Path::new(path).parent().unwrap_or(Path::new(""));
+
+        Self::from_json(&data, directory)
+    }
+
+    // Relative image paths are resolved from the given directory.
+    pub fn from_json(data: &str, directory: &Path) -> Self {
+        let sheet: Value =
+            serde_json::from_str(data).expect("Failed to parse Aseprite sprite sheet");
+        let meta = &sheet["meta"];
+
+        let width = meta["size"]["w"]
+            .as_f64()
+            .expect("Missing or invalid Aseprite field size") as f32;
+        let height = meta["size"]["h"]
+            .as_f64()
+            .expect("Missing or invalid Aseprite field size") as f32;
+
+        let frames: Vec<Frame> = match &sheet["frames"] {
+            Value::Object(frames) => frames
+                .values()
+                .map(|frame| self::frame(frame, width, height))
+                .collect(),
+            frames => array(frames)
+                .iter()
+                .map(|frame| self::frame(frame, width, height))
+                .collect(),
+        };
+
+        let animations = array(&meta["frameTags"])
+            .iter()
+            .map(|tag| {
+                (
+                    string(tag, "name"),
+                    rc::Rc::new(self::animation(tag, &frames)),
+                )
+            })
+            .collect();
+
+        let slices = array(&meta["slices"])
+            .iter()
+            .map(|slice| {
+                let keys = array(&slice["keys"]).iter().map(slice_key).collect();
+                (string(slice, "name"), Slice { keys })
+            })
+            .collect();
+
+        Self {
+            image: meta["image"].as_str().map(|image| directory.join(image)),
+            width,
+            height,
+            frames,
+            animations,
+            slices,
+        }
+    }
+
+    // Plays every frame in the sheet, for exports without tags.
+    pub fn all_frames(&self, mode: PlayMode) -> Animation {
+        Animation::new(self.frames.clone(), mode)
+    }
+
+    // The texture region covered by a slice on the given frame.
+    pub fn slice_region(&self, name: &str, frame: usize) -> Option<Region> {
+        let key = self.slices.get(name)?.key(frame)?;
+        let frame = self.frames.get(frame)?;
+
+        Some(Region::from_pixels(
+            frame.region.x * self.width + key.x - frame.x_offset,
+            frame.region.y * self.height + key.y - frame.y_offset,
+            key.width,
+            key.height,
+            self.width,
+            self.height,
+        ))
+    }
+}
+
+fn array(value: &Value) -> &[Value] {
+    value.as_array().map(|array| &array[..]).unwrap_or(&[])
+}
+
+fn string(value: &Value, name: &str) -> String {
+    value[name].as_str().unwrap_or_default().to_string()
+}
+
+fn f32(value: &Value, name: &str) -> f32 {
+    value[name].as_f64().unwrap_or(0.0) as f32
+}
+
+fn rect(value: &Value) -> [f32; 4] {
+    [
+        f32(value, "x"),
+        f32(value, "y"),
+        f32(value, "w"),
+        f32(value, "h"),
+    ]
+}
+
+fn frame(value: &Value, texture_width: f32, texture_height: f32) -> Frame {
+    let [x, y, width, height] = rect(&value["frame"]);
+    if value["rotated"].as_bool().unwrap_or(false) {
+        panic!("Rotated Aseprite frames are not supported");
+    }
+
+    let [x_offset, y_offset, _, _] = rect(&value["spriteSourceSize"]);
+
+    Frame {
+        region: Region::from_pixels(x, y, width, height, texture_width, texture_height),
+        duration: f32(value, "duration") / 1000.0,
+        x_offset,
+        y_offset,
+        width,
+        height,
+    }
+}
+
+fn animation(tag: &Value, frames: &[Frame]) -> Animation {
+    let from = tag["from"].as_u64().unwrap_or(0) as usize;
+    let to = tag["to"].as_u64().unwrap_or(0) as usize;
+    let mut tag_frames = frames
+        .get(from..=to)
+        .unwrap_or_else(|| panic!("Invalid Aseprite tag {}", string(tag, "name")))
+        .to_vec();
+
+    let mode = match tag["direction"].as_str().unwrap_or("forward") {
+        "reverse" => {
+            tag_frames.reverse();
+            PlayMode::Loop
+        }
+        "pingpong" => PlayMode::PingPong,
+        "pingpong_reverse" => {
+            tag_frames.reverse();
+            PlayMode::PingPong
+        }
+        _ => PlayMode::Loop,
+    };
+
+    // Newer versions of Aseprite export the repeat count as a string, zero meaning forever.
+    let repeat = match &tag["repeat"] {
+        Value::String(repeat) => repeat.parse().ok(),
+        repeat => repeat.as_u64().map(|repeat| repeat as u32),
+    }
+    .filter(|&repeat| repeat > 0);
+
+    Animation {
+        frames: tag_frames,
+        mode,
+        repeat,
+    }
+}
+
+fn slice_key(value: &Value) -> SliceKey {
+    let [x, y, width, height] = rect(&value["bounds"]);
+
+    SliceKey {
+        frame: value["frame"].as_u64().unwrap_or(0) as usize,
+        x,
+        y,
+        width,
+        height,
+        center: value.get("center").map(rect),
+        pivot: value
+            .get("pivot")
+            .map(|pivot| [f32(pivot, "x"), f32(pivot, "y")]),
+    }
+}
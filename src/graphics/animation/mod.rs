@@ -0,0 +1,246 @@
+pub mod aseprite;
+
+use std::rc;
+
+use super::sprite_batch::{Region, Sprite};
+
+// Frames shorter than this are stretched so a single update can't spin forever.
+const MIN_FRAME_DURATION: f32 = 0.001;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frame {
+    pub region: Region,
+    // In seconds.
+    pub duration: f32,
+    // Position of the frame within its untrimmed bounds and its size, in pixels.
+    pub x_offset: f32,
+    pub y_offset: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Frame {
+    pub fn new(region: Region, duration: f32, width: f32, height: f32) -> Self {
+        Self {
+            region,
+            duration,
+            x_offset: 0.0,
+            y_offset: 0.0,
+            width,
+            height,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayMode {
+    Loop,
+    PingPong,
+    Once,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimationEvent {
+    // Reached the end of a pass and started another one.
+    Looped,
+    // Played the last frame of the last pass, the sprite stays on that frame.
+    Finished,
+}
+
+#[derive(Clone, Debug)]
+pub struct Animation {
+    pub frames: Vec<Frame>,
+    pub mode: PlayMode,
+    // Number of passes before finishing, or forever if None. Each direction of
+    // a ping-pong counts as a pass. Once always finishes after one pass.
+    pub repeat: Option<u32>,
+}
+
+impl Animation {
+    pub fn new(frames: Vec<Frame>, mode: PlayMode) -> Self {
+        Self {
+            frames,
+            mode,
+            repeat: None,
+        }
+    }
+
+    // Takes frames from a texture split into a grid of equally sized cells, left to right then top to bottom.
+    pub fn from_grid(
+        texture_width: f32,
+        texture_height: f32,
+        frame_width: f32,
+        frame_height: f32,
+        frame_count: usize,
+        duration: f32,
+        mode: PlayMode,
+    ) -> Self {
+        let columns = ((texture_width / frame_width) as usize).max(1);
+        let frames = (0..frame_count)
+            .map(|index| {
+                let x = (index % columns) as f32 * frame_width;
+                let y = (index / columns) as f32 * frame_height;
+                let region = Region::from_pixels(
+                    x,
+                    y,
+                    frame_width,
+                    frame_height,
+                    texture_width,
+                    texture_height,
+                );
+
+                Frame::new(region, duration, frame_width, frame_height)
+            })
+            .collect();
+
+        Self::new(frames, mode)
+    }
+
+    // Length of a single pass in seconds.
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|frame| frame.duration).sum()
+    }
+}
+
+pub struct AnimatedSprite {
+    animation: rc::Rc<Animation>,
+    frame: usize,
+    time: f32,
+    forward: bool,
+    passes: u32,
+    finished: bool,
+    pub speed: f32,
+}
+
+impl AnimatedSprite {
+    pub fn new(animation: rc::Rc<Animation>) -> Self {
+        Self {
+            animation,
+            frame: 0,
+            time: 0.0,
+            forward: true,
+            passes: 0,
+            finished: false,
+            speed: 1.0,
+        }
+    }
+
+    // Switches to another animation, playing it from the start unless it's already playing.
+    pub fn play(&mut self, animation: &rc::Rc<Animation>) {
+        if !rc::Rc::ptr_eq(&self.animation, animation) {
+            self.animation = animation.clone();
+            self.restart();
+        }
+    }
+
+    pub fn restart(&mut self) {
+        self.frame = 0;
+        self.time = 0.0;
+        self.forward = true;
+        self.passes = 0;
+        self.finished = false;
+    }
+
+    pub fn animation(&self) -> &rc::Rc<Animation> {
+        &self.animation
+    }
+
+    pub fn frame_index(&self) -> usize {
+        self.frame
+    }
+
+    // None when the animation has no frames, eg: from an Aseprite tag covering none.
+    pub fn frame(&self) -> Option<&Frame> {
+        self.animation.frames.get(self.frame)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    // Returns the most important event that happened during the update, if any.
+    pub fn update(&mut self, delta_time: f32) -> Option<AnimationEvent> {
+        if self.finished || self.animation.frames.is_empty() {
+            return None;
+        }
+
+        self.time += delta_time * self.speed;
+
+        let mut event = None;
+        while !self.finished {
+            let duration = self.animation.frames[self.frame]
+                .duration
+                .max(MIN_FRAME_DURATION);
+            if self.time < duration {
+                break;
+            }
+
+            self.time -= duration;
+            if let Some(new_event) = self.advance() {
+                event = Some(new_event);
+            }
+        }
+
+        if self.finished {
+            self.time = 0.0;
+        }
+
+        event
+    }
+
+    // The sprite for the current frame, with (x, y) being the top left of the untrimmed frame.
+    // None when there's no frame to draw.
+    pub fn sprite(&self, x: f32, y: f32, z: f32) -> Option<Sprite> {
+        let frame = self.frame()?;
+
+        Some(Sprite {
+            x: x + frame.x_offset,
+            y: y + frame.y_offset,
+            z,
+            width: frame.width,
+            height: frame.height,
+            region: frame.region,
+        })
+    }
+
+    fn advance(&mut self) -> Option<AnimationEvent> {
+        let last = self.animation.frames.len() - 1;
+
+        if self.forward && self.frame < last {
+            self.frame += 1;
+            return None;
+        }
+        if !self.forward && self.frame > 0 {
+            self.frame -= 1;
+            return None;
+        }
+
+        // Reached the end of a pass.
+        self.passes += 1;
+        let finished = match self.animation.mode {
+            PlayMode::Once => true,
+            _ => self
+                .animation
+                .repeat
+                .is_some_and(|repeat| self.passes >= repeat),
+        };
+        if finished {
+            self.finished = true;
+            return Some(AnimationEvent::Finished);
+        }
+
+        match self.animation.mode {
+            PlayMode::PingPong if last > 0 => {
+                self.forward = !self.forward;
+                if self.forward {
+                    self.frame += 1;
+                } else {
+                    self.frame -= 1;
+                }
+            }
+            _ => self.frame = 0,
+        }
+
+        Some(AnimationEvent::Looped)
+    }
+}
@@ -3,5 +3,10 @@ use super::*;
 pub trait App {
     fn new(resources: &mut Resources) -> Self;
     fn update(&mut self, resources: &mut Resources, input: &mut Input, delta_time: f32);
+    fn compute(&mut self, _compute: &Compute) {}
+    // Called after compute and before draw, to draw into render targets.
+    fn draw_offscreen(&mut self, _offscreen: &Offscreen) {}
+    // Called before update when the window moves to a monitor with a different scale factor.
+    fn scale_factor_changed(&mut self, _resources: &mut Resources, _scale_factor: f32) {}
     fn draw(&mut self, draw: &Draw);
 }
@@ -0,0 +1,380 @@
+use std::{ffi::CStr, io, mem, rc};
+
+use ash::{util::*, vk};
+
+use super::{texture, vk_base::*, vk_resources::*, Compute, Resources};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BindingType {
+    StorageBuffer,
+    StorageImage,
+    Texture,
+}
+
+// Resources given to a dispatch, where each one's index is its binding number.
+pub enum Binding<'a> {
+    StorageBuffer(&'a StorageBuffer),
+    StorageImage(&'a texture::Texture),
+    Texture(&'a texture::Texture),
+}
+
+// A buffer that compute shaders can read and write, which can also be used
+// as a vertex, index or indirect draw buffer afterwards.
+pub struct StorageBuffer {
+    device_data: rc::Rc<device_data::DeviceData>,
+    buffer: buffer::Buffer,
+    size: u64,
+}
+
+impl StorageBuffer {
+    pub fn new<T: Copy>(resources: &Resources, data: &[T]) -> Self {
+        let buffer = unsafe {
+            buffer::Buffer::new(
+                data,
+                resources.base.device_data.clone(),
+                vk::BufferUsageFlags::STORAGE_BUFFER
+                    | vk::BufferUsageFlags::VERTEX_BUFFER
+                    | vk::BufferUsageFlags::INDEX_BUFFER
+                    | vk::BufferUsageFlags::INDIRECT_BUFFER
+                    | vk::BufferUsageFlags::TRANSFER_DST,
+            )
+        };
+
+        Self {
+            device_data: resources.base.device_data.clone(),
+            buffer,
+            size: mem::size_of_val(data) as u64,
+        }
+    }
+
+    // The data must be no bigger than what the buffer was created with.
+    pub fn set_data<T: Copy>(&mut self, data: &[T]) {
+        assert!(
+            mem::size_of_val(data) as u64 <= self.size,
+            "Storage buffer data doesn't fit in the buffer"
+        );
+
+        unsafe {
+            self.device_data.device.device_wait_idle().unwrap();
+            self.buffer.set_data(data);
+        }
+    }
+
+    // Writes the data as part of the frame's compute work instead, so it doesn't wait for the
+    // GPU, eg: to reset a counter every frame. It's visible to the dispatches and draws after
+    // it, and has to be no bigger than 65536 bytes.
+    pub fn update<T: Copy>(&self, compute: &Compute, data: &[T]) {
+        let size = mem::size_of_val(data);
+        assert!(
+            size as u64 <= self.size && size <= 65536 && size.is_multiple_of(4),
+            "Storage buffer data doesn't fit in the buffer, or isn't a multiple of 4 bytes"
+        );
+
+        unsafe {
+            compute.device.cmd_update_buffer(
+                compute.command_buffer,
+                self.vk_buffer(),
+                0,
+                std::slice::from_raw_parts(data.as_ptr() as *const u8, size),
+            );
+            compute.memory_barrier(
+                vk::PipelineStageFlags::TRANSFER,
+                vk::AccessFlags::TRANSFER_WRITE,
+                vk::PipelineStageFlags::COMPUTE_SHADER
+                    | vk::PipelineStageFlags::DRAW_INDIRECT
+                    | vk::PipelineStageFlags::VERTEX_INPUT
+                    | vk::PipelineStageFlags::VERTEX_SHADER
+                    | vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::AccessFlags::SHADER_READ
+                    | vk::AccessFlags::SHADER_WRITE
+                    | vk::AccessFlags::INDIRECT_COMMAND_READ
+                    | vk::AccessFlags::VERTEX_ATTRIBUTE_READ,
+            );
+        }
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn vk_buffer(&self) -> vk::Buffer {
+        self.buffer.vk_buffer()
+    }
+}
+
+pub struct ComputePipeline {
+    device_data: rc::Rc<device_data::DeviceData>,
+
+    shader_module: vk::ShaderModule,
+
+    pipelines: Vec<vk::Pipeline>,
+    pipeline_layout: vk::PipelineLayout,
+    push_constant_size: u32,
+
+    binding_types: Vec<BindingType>,
+    descriptor_sets: Vec<vk::DescriptorSet>,
+    descriptor_set_layouts: [vk::DescriptorSetLayout; 1],
+    descriptor_pool: vk::DescriptorPool,
+}
+
+impl ComputePipeline {
+    // Like graphics pipelines, each compute pipeline has a single descriptor set,
+    // so it should only be dispatched once per frame.
+    pub fn new(
+        resources: &Resources,
+        spv: &[u8],
+        binding_types: &[BindingType],
+        push_constant_size: u32,
+    ) -> Self {
+        let descriptor_layout_bindings: Vec<vk::DescriptorSetLayoutBinding> = binding_types
+            .iter()
+            .enumerate()
+            .map(|(binding, &binding_type)| vk::DescriptorSetLayoutBinding {
+                binding: binding as u32,
+                descriptor_type: Self::descriptor_type(binding_type),
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::COMPUTE,
+                ..Default::default()
+            })
+            .collect();
+
+        unsafe {
+            let device = &resources.base.device_data.device;
+
+            let descriptor_sizes: Vec<vk::DescriptorPoolSize> = descriptor_layout_bindings
+                .iter()
+                .map(|binding| vk::DescriptorPoolSize {
+                    ty: binding.descriptor_type,
+                    descriptor_count: binding.descriptor_count,
+                })
+                .collect();
+            // Pools can't be created without any sizes, even if no sets use them.
+            let descriptor_sizes = if descriptor_sizes.is_empty() {
+                vec![vk::DescriptorPoolSize {
+                    ty: vk::DescriptorType::STORAGE_BUFFER,
+                    descriptor_count: 1,
+                }]
+            } else {
+                descriptor_sizes
+            };
+            let descriptor_pool_info = vk::DescriptorPoolCreateInfo::builder()
+                .pool_sizes(&descriptor_sizes)
+                .max_sets(1)
+                .build();
+            let descriptor_pool = device
+                .create_descriptor_pool(&descriptor_pool_info, None)
+                .unwrap();
+
+            let descriptor_info = vk::DescriptorSetLayoutCreateInfo::builder()
+                .bindings(&descriptor_layout_bindings)
+                .build();
+            let descriptor_set_layouts = [device
+                .create_descriptor_set_layout(&descriptor_info, None)
+                .unwrap()];
+
+            let descriptor_alloc_info = vk::DescriptorSetAllocateInfo::builder()
+                .descriptor_pool(descriptor_pool)
+                .set_layouts(&descriptor_set_layouts)
+                .build();
+            let descriptor_sets = device
+                .allocate_descriptor_sets(&descriptor_alloc_info)
+                .unwrap();
+
+            let mut spv_file = io::Cursor::new(spv);
+            let code = read_spv(&mut spv_file).expect("Failed to read compute shader spv file");
+            let shader_info = vk::ShaderModuleCreateInfo::builder().code(&code).build();
+            let shader_module = device
+                .create_shader_module(&shader_info, None)
+                .expect("Failed to create compute shader module");
+
+            let push_constant_ranges = [vk::PushConstantRange {
+                stage_flags: vk::ShaderStageFlags::COMPUTE,
+                offset: 0,
+                size: push_constant_size,
+            }];
+            let push_constant_ranges = if push_constant_size > 0 {
+                &push_constant_ranges[..]
+            } else {
+                &[]
+            };
+            let layout_create_info = vk::PipelineLayoutCreateInfo::builder()
+                .set_layouts(&descriptor_set_layouts)
+                .push_constant_ranges(push_constant_ranges)
+                .build();
+            let pipeline_layout = device
+                .create_pipeline_layout(&layout_create_info, None)
+                .unwrap();
+
+            let shader_entry_name = CStr::from_bytes_with_nul_unchecked(b"main\0");
+            let pipeline_info = vk::ComputePipelineCreateInfo::builder()
+                .stage(vk::PipelineShaderStageCreateInfo {
+                    module: shader_module,
+                    p_name: shader_entry_name.as_ptr(),
+                    stage: vk::ShaderStageFlags::COMPUTE,
+                    ..Default::default()
+                })
+                .layout(pipeline_layout)
+                .build();
+            let pipelines = device
+                .create_compute_pipelines(vk::PipelineCache::null(), &[pipeline_info], None)
+                .unwrap();
+
+            Self {
+                device_data: resources.base.device_data.clone(),
+
+                shader_module,
+
+                pipelines,
+                pipeline_layout,
+                push_constant_size,
+
+                binding_types: binding_types.to_vec(),
+                descriptor_sets,
+                descriptor_set_layouts,
+                descriptor_pool,
+            }
+        }
+    }
+
+    fn descriptor_type(binding_type: BindingType) -> vk::DescriptorType {
+        match binding_type {
+            BindingType::StorageBuffer => vk::DescriptorType::STORAGE_BUFFER,
+            BindingType::StorageImage => vk::DescriptorType::STORAGE_IMAGE,
+            BindingType::Texture => vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+        }
+    }
+
+    // Use () for push constants when the pipeline has none.
+    pub fn dispatch<T: Copy>(
+        &self,
+        compute: &Compute,
+        bindings: &[Binding],
+        push_constants: &T,
+        group_count: [u32; 3],
+    ) {
+        assert_eq!(
+            bindings.len(),
+            self.binding_types.len(),
+            "Dispatch bindings don't match the compute pipeline's bindings"
+        );
+        assert_eq!(
+            mem::size_of::<T>() as u32,
+            self.push_constant_size,
+            "Dispatch push constants don't match the compute pipeline's push constant size"
+        );
+
+        let buffer_infos: Vec<vk::DescriptorBufferInfo> = bindings
+            .iter()
+            .map(|binding| match binding {
+                Binding::StorageBuffer(buffer) => vk::DescriptorBufferInfo {
+                    buffer: buffer.vk_buffer(),
+                    offset: 0,
+                    range: vk::WHOLE_SIZE,
+                },
+                _ => vk::DescriptorBufferInfo::default(),
+            })
+            .collect();
+
+        let write_descriptor_sets: Vec<vk::WriteDescriptorSet> = bindings
+            .iter()
+            .zip(&self.binding_types)
+            .enumerate()
+            .map(|(index, (binding, &binding_type))| {
+                let mut write = vk::WriteDescriptorSet {
+                    dst_set: self.descriptor_sets[0],
+                    dst_binding: index as u32,
+                    descriptor_count: 1,
+                    descriptor_type: Self::descriptor_type(binding_type),
+                    ..Default::default()
+                };
+
+                match (binding, binding_type) {
+                    (Binding::StorageBuffer(_), BindingType::StorageBuffer) => {
+                        write.p_buffer_info = &buffer_infos[index];
+                    }
+                    (Binding::StorageImage(texture), BindingType::StorageImage) => {
+                        assert!(
+                            texture.is_storage(),
+                            "Storage image bindings need a texture made with Texture::storage"
+                        );
+                        write.p_image_info = &texture.descriptor;
+                    }
+                    (Binding::Texture(texture), BindingType::Texture) => {
+                        write.p_image_info = &texture.descriptor;
+                    }
+                    _ => panic!("Dispatch binding {} has the wrong type", index),
+                }
+
+                write
+            })
+            .collect();
+
+        unsafe {
+            compute
+                .device
+                .update_descriptor_sets(&write_descriptor_sets, &[]);
+
+            compute.device.cmd_bind_descriptor_sets(
+                compute.command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                self.pipeline_layout,
+                0,
+                &self.descriptor_sets[..],
+                &[],
+            );
+            compute.device.cmd_bind_pipeline(
+                compute.command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                self.pipelines[0],
+            );
+
+            if self.push_constant_size > 0 {
+                compute.device.cmd_push_constants(
+                    compute.command_buffer,
+                    self.pipeline_layout,
+                    vk::ShaderStageFlags::COMPUTE,
+                    0,
+                    std::slice::from_raw_parts(
+                        push_constants as *const T as *const u8,
+                        mem::size_of::<T>(),
+                    ),
+                );
+            }
+
+            compute.device.cmd_dispatch(
+                compute.command_buffer,
+                group_count[0],
+                group_count[1],
+                group_count[2],
+            );
+        }
+
+        compute.dispatched.set(true);
+    }
+}
+
+impl Drop for ComputePipeline {
+    fn drop(&mut self) {
+        unsafe {
+            self.device_data.device.device_wait_idle().unwrap();
+
+            for pipeline in &self.pipelines {
+                self.device_data.device.destroy_pipeline(*pipeline, None);
+            }
+            self.device_data
+                .device
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+            self.device_data
+                .device
+                .destroy_shader_module(self.shader_module, None);
+            for &descriptor_set_layout in self.descriptor_set_layouts.iter() {
+                self.device_data
+                    .device
+                    .destroy_descriptor_set_layout(descriptor_set_layout, None);
+            }
+            self.device_data
+                .device
+                .destroy_descriptor_pool(self.descriptor_pool, None);
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use std::{fs, rc};
+
+use super::texture;
+
+// Vertex and fragment shaders that sprite batches draw with, written against the same
+// interface as the built-in sprite shaders:
+//
+//     layout (push_constant) uniform Material {
+//         vec4 params[4];
+//     } material;
+//     layout (binding = 1) uniform sampler2D sampler_color;
+//     layout (binding = 2) uniform UBO {
+//         mat4 projection_matrix;
+//     } ubo;
+//     layout (binding = 3) uniform sampler2D palette;
+//
+// Sampler_color is the batch's texture, and the material's textures follow it, one binding
+// each from 3 onwards. The vertex shader gets the sprite's position at location 0 and its uv at
+// location 1. Both stages can read the material's params.
+pub struct Shader {
+    pub vertex_spv: rc::Rc<[u8]>,
+    pub fragment_spv: rc::Rc<[u8]>,
+}
+
+impl Shader {
+    pub fn new(vertex_spv: &[u8], fragment_spv: &[u8]) -> Self {
+        Self {
+            vertex_spv: vertex_spv.into(),
+            fragment_spv: fragment_spv.into(),
+        }
+    }
+
+    pub fn from_files(vertex_path: &str, fragment_path: &str) -> Self {
+        Self::new(&read_spv_file(vertex_path), &read_spv_file(fragment_path))
+    }
+
+    // Most effects only change how sprites are colored, so they keep the sprite vertex shader.
+    pub fn from_fragment(fragment_spv: &[u8]) -> Self {
+        Self::new(
+            include_bytes!("../../shader/texture.vert.spv"),
+            fragment_spv,
+        )
+    }
+
+    pub fn from_fragment_file(fragment_path: &str) -> Self {
+        Self::from_fragment(&read_spv_file(fragment_path))
+    }
+
+    // Draws the batch's texture as it is, discarding pixels that aren't fully opaque.
+    pub fn sprite() -> Self {
+        Self::from_fragment(include_bytes!("../../shader/texture.frag.spv"))
+    }
+
+    // Keeps the texture's alpha, for materials that blend.
+    pub fn translucent_sprite() -> Self {
+        Self::from_fragment(include_bytes!("../../shader/texture_alpha.frag.spv"))
+    }
+}
+
+// How sprites are combined with what's behind them. Sprites that blend don't write depth,
+// so they should be drawn after what they're in front of.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Blend {
+    Opaque,
+    Alpha,
+    Additive,
+}
+
+// A shader and the values it's drawn with, eg: a dissolve's threshold, an outline's color or
+// the palette to swap colors with. Materials can share a shader by sharing its Rc.
+#[derive(Clone)]
+pub struct Material {
+    pub shader: rc::Rc<Shader>,
+    pub textures: Vec<rc::Rc<texture::Texture>>,
+    pub params: [[f32; 4]; 4],
+    pub blend: Blend,
+}
+
+impl Material {
+    pub fn new(shader: rc::Rc<Shader>) -> Self {
+        Self {
+            shader,
+            textures: Vec::new(),
+            params: [[0.0; 4]; 4],
+            blend: Blend::Opaque,
+        }
+    }
+
+    pub fn sprite() -> Self {
+        Self::new(rc::Rc::new(Shader::sprite()))
+    }
+
+    // eg: for sprites with soft edges or that fade out.
+    pub fn translucent_sprite() -> Self {
+        Self {
+            blend: Blend::Alpha,
+            ..Self::new(rc::Rc::new(Shader::translucent_sprite()))
+        }
+    }
+
+    // Whether a batch drawing with this material can draw with the other one without
+    // rebuilding its pipeline.
+    pub(super) fn is_compatible(&self, other: &Material) -> bool {
+        rc::Rc::ptr_eq(&self.shader, &other.shader)
+            && self.textures.len() == other.textures.len()
+            && self.blend == other.blend
+    }
+}
+
+fn read_spv_file(path: &str) -> Vec<u8> {
+    fs::read(path).unwrap_or_else(|_| panic!("Failed to load file {}", path))
+}
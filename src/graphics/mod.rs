@@ -1,18 +1,32 @@
+pub mod animation;
 pub mod app;
+pub mod color;
+pub mod compute;
+pub mod material;
+pub mod particles;
+pub mod path;
+pub mod post_process;
+pub mod render_target;
+#[cfg(feature = "shader-compiler")]
+pub mod shader_compiler;
+pub mod shape_batch;
 pub mod sprite_batch;
 pub mod texture;
+pub mod tilemap;
+pub mod virtual_resolution;
+pub mod window;
 
 mod mat4;
+mod vec2;
 mod vk_base;
 mod vk_resources;
 
-use std::{default::Default, ffi::CStr, mem, time};
+use std::{cell, default::Default, mem, time};
 
-use ash::util::*;
 use ash::vk;
 
-pub use vk_base::input::*;
 use vk_base::*;
+pub use vk_base::{actions::*, gamepad::*, input::*, mouse::*, physical_key::*, touch::*};
 use vk_resources::*;
 use winit::event_loop::EventLoop;
 use winit::window::WindowBuilder;
@@ -21,6 +35,140 @@ pub struct Draw<'a> {
     device: &'a ash::Device,
     command_buffer: vk::CommandBuffer,
     resources: &'a Resources,
+    // The part of the render pass's target being drawn to.
+    viewports: [vk::Viewport; 1],
+    scissors: [vk::Rect2D; 1],
+    projection_matrix_buffer_descriptor: vk::DescriptorBufferInfo,
+    // The size the projection covers, in the pixels sprites are measured in, eg: to cull with.
+    view_width: f32,
+    view_height: f32,
+}
+
+// Render targets are drawn into before the frame's render pass begins.
+pub struct Offscreen<'a> {
+    device: &'a ash::Device,
+    command_buffer: vk::CommandBuffer,
+    resources: &'a Resources,
+}
+
+impl Offscreen<'_> {
+    pub fn resources(&self) -> &Resources {
+        self.resources
+    }
+
+    // Clears the target first when given a color, otherwise draws over what's already there.
+    // Sprites drawn by the closure are measured in the target's pixels.
+    pub fn render(
+        &self,
+        target: &render_target::RenderTarget,
+        clear_color: Option<color::Color>,
+        draw: impl FnOnce(&Draw),
+    ) {
+        self.render_with_projection(
+            target,
+            clear_color,
+            target.projection_matrix_buffer_descriptor(),
+            [target.width() as f32, target.height() as f32],
+            draw,
+        );
+    }
+
+    // For the app's draws into the virtual screen or the post processing scene, which use
+    // the screen's projection.
+    fn render_with_projection(
+        &self,
+        target: &render_target::RenderTarget,
+        clear_color: Option<color::Color>,
+        projection_matrix_buffer_descriptor: vk::DescriptorBufferInfo,
+        [view_width, view_height]: [f32; 2],
+        draw: impl FnOnce(&Draw),
+    ) {
+        unsafe {
+            target.begin(self.device, self.command_buffer, clear_color);
+
+            draw(&Draw {
+                device: self.device,
+                command_buffer: self.command_buffer,
+                resources: self.resources,
+                viewports: [target.vk_viewport()],
+                scissors: [target.extent().into()],
+                projection_matrix_buffer_descriptor,
+                view_width,
+                view_height,
+            });
+
+            target.end(self.device, self.command_buffer);
+        }
+    }
+}
+
+// Compute work is recorded before the render pass begins.
+pub struct Compute<'a> {
+    device: &'a ash::Device,
+    command_buffer: vk::CommandBuffer,
+    resources: &'a Resources,
+    dispatched: cell::Cell<bool>,
+}
+
+impl Compute<'_> {
+    pub fn resources(&self) -> &Resources {
+        self.resources
+    }
+
+    // Makes the writes of earlier dispatches visible to later ones.
+    pub fn barrier(&self) {
+        unsafe {
+            self.memory_barrier(
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::AccessFlags::SHADER_WRITE,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE,
+            );
+        }
+    }
+
+    // Makes every dispatch's writes visible to the draws that follow.
+    unsafe fn finish(&self) {
+        if !self.dispatched.get() {
+            return;
+        }
+
+        self.memory_barrier(
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::AccessFlags::SHADER_WRITE,
+            vk::PipelineStageFlags::DRAW_INDIRECT
+                | vk::PipelineStageFlags::VERTEX_INPUT
+                | vk::PipelineStageFlags::VERTEX_SHADER
+                | vk::PipelineStageFlags::FRAGMENT_SHADER,
+            vk::AccessFlags::INDIRECT_COMMAND_READ
+                | vk::AccessFlags::INDEX_READ
+                | vk::AccessFlags::VERTEX_ATTRIBUTE_READ
+                | vk::AccessFlags::SHADER_READ,
+        );
+    }
+
+    unsafe fn memory_barrier(
+        &self,
+        src_stage: vk::PipelineStageFlags,
+        src_access: vk::AccessFlags,
+        dst_stage: vk::PipelineStageFlags,
+        dst_access: vk::AccessFlags,
+    ) {
+        let memory_barrier = vk::MemoryBarrier {
+            src_access_mask: src_access,
+            dst_access_mask: dst_access,
+            ..Default::default()
+        };
+        self.device.cmd_pipeline_barrier(
+            self.command_buffer,
+            src_stage,
+            dst_stage,
+            vk::DependencyFlags::empty(),
+            &[memory_barrier],
+            &[],
+            &[],
+        );
+    }
 }
 
 pub struct Graphics {
@@ -31,20 +179,217 @@ pub struct Graphics {
 
     needs_resize: bool,
     needs_first_draw: bool,
+
+    recorder: Option<recording::Recorder>,
+    replay: Option<recording::Replay>,
+    gamepad_backend: Option<Box<dyn GamepadBackend>>,
+
+    cursor_sprite: Option<window::CursorSprite>,
 }
 
 pub struct Resources {
     render_pass: render_pass::RenderPass,
     viewports: [vk::Viewport; 1],
     scissors: [vk::Rect2D; 1],
+    // Counts the frames drawn, so pipelines know when their descriptor sets are free again.
+    frame: u64,
 
     projection_matrix: mat4::Mat4,
     projection_matrix_buffer: buffer::Buffer,
     projection_matrix_buffer_descriptor: vk::DescriptorBufferInfo,
+    // Covers the whole window even while there's a virtual screen, eg: for the cursor.
+    window_projection_matrix: mat4::Mat4,
+    window_projection_matrix_buffer: buffer::Buffer,
+    window_projection_matrix_buffer_descriptor: vk::DescriptorBufferInfo,
+
+    scale_factor: f32,
+    physical_pixels: bool,
+    window: window::WindowControl,
+
+    virtual_screen: Option<virtual_resolution::VirtualScreen>,
+    post_process: post_process::PostProcess,
+
+    #[cfg(feature = "shader-compiler")]
+    shader_watcher: shader_compiler::ShaderWatcher,
 
     base: vk_base::VkBase,
 }
 
+impl Resources {
+    pub fn window(&mut self) -> &mut window::WindowControl {
+        &mut self.window
+    }
+
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
+    pub fn uses_physical_pixels(&self) -> bool {
+        self.physical_pixels
+    }
+
+    // How many physical pixels make up one of the pixels sprites and the mouse are measured in.
+    pub fn pixel_scale(&self) -> f32 {
+        if self.physical_pixels {
+            1.0
+        } else {
+            self.scale_factor
+        }
+    }
+
+    // The size of the window in the pixels sprites and the mouse are measured in,
+    // or the virtual resolution while there is one.
+    pub fn screen_width(&self) -> f32 {
+        match &self.virtual_screen {
+            Some(virtual_screen) => virtual_screen.width() as f32,
+            None => self.base.surface_data.resolution.width as f32 / self.pixel_scale(),
+        }
+    }
+
+    pub fn screen_height(&self) -> f32 {
+        match &self.virtual_screen {
+            Some(virtual_screen) => virtual_screen.height() as f32,
+            None => self.base.surface_data.resolution.height as f32 / self.pixel_scale(),
+        }
+    }
+
+    // Draws everything at a fixed size, eg: 320x180 for pixel art, which is then upscaled
+    // to the window without filtering. Sprites and the mouse are measured in virtual pixels,
+    // mouse positions over the black bars being outside of the screen.
+    pub fn set_virtual_resolution(
+        &mut self,
+        width: u32,
+        height: u32,
+        scaling: virtual_resolution::Scaling,
+    ) {
+        match &mut self.virtual_screen {
+            Some(virtual_screen)
+                if virtual_screen.width() == width && virtual_screen.height() == height =>
+            {
+                virtual_screen.set_scaling(scaling);
+            }
+            _ => {
+                // Dropped first so both don't exist at once.
+                self.virtual_screen = None;
+                self.virtual_screen = Some(virtual_resolution::VirtualScreen::new(
+                    self, width, height, scaling,
+                ));
+                unsafe { self.update_projection() };
+            }
+        }
+    }
+
+    // Goes back to drawing straight to the window.
+    pub fn clear_virtual_resolution(&mut self) {
+        if self.virtual_screen.take().is_some() {
+            unsafe { self.update_projection() };
+        }
+    }
+
+    pub fn virtual_resolution(&self) -> Option<(u32, u32)> {
+        self.virtual_screen
+            .as_ref()
+            .map(|virtual_screen| (virtual_screen.width(), virtual_screen.height()))
+    }
+
+    pub fn virtual_scaling(&self) -> Option<virtual_resolution::Scaling> {
+        self.virtual_screen
+            .as_ref()
+            .map(|virtual_screen| virtual_screen.scaling())
+    }
+
+    // Where the virtual screen is currently drawn in the window.
+    pub fn virtual_viewport(&self) -> Option<virtual_resolution::Viewport> {
+        let resolution = self.base.surface_data.resolution;
+
+        self.virtual_screen
+            .as_ref()
+            .map(|virtual_screen| virtual_screen.viewport(resolution.width, resolution.height))
+    }
+
+    // Full screen effects applied in order to what the app draws, before it reaches the
+    // window or is upscaled from the virtual screen. An empty list turns them off.
+    pub fn set_post_effects(&mut self, effects: Vec<post_process::Effect>) {
+        self.post_process.set_effects(effects);
+    }
+
+    pub fn post_effects(&self) -> &[post_process::Effect] {
+        self.post_process.effects()
+    }
+
+    // Changed settings apply to the next frame, while effects that changed kind, or custom
+    // effects given a different shader, are rebuilt.
+    pub fn post_effects_mut(&mut self) -> &mut Vec<post_process::Effect> {
+        self.post_process.effects_mut()
+    }
+
+    // Shaders loaded from here are recompiled when their files change. Custom post effects
+    // using them are rebuilt on their own, but sprite batches aren't: the app has to call
+    // SpriteBatch::reload_shaders on each batch drawn with them, eg: at the start of update,
+    // or the batch keeps drawing with the shaders it was made with.
+    #[cfg(feature = "shader-compiler")]
+    pub fn shader_watcher(&mut self) -> &mut shader_compiler::ShaderWatcher {
+        &mut self.shader_watcher
+    }
+
+    // The size of the window in logical pixels, or physical ones when using them, ignoring
+    // the virtual screen.
+    fn window_size(&self) -> (f32, f32) {
+        let resolution = self.base.surface_data.resolution;
+
+        (
+            resolution.width as f32 / self.pixel_scale(),
+            resolution.height as f32 / self.pixel_scale(),
+        )
+    }
+
+    // The offset and scale that take the pixels sprites and the mouse are measured in to the
+    // window's physical pixels.
+    fn screen_transform(&self) -> ([f32; 2], f32) {
+        match self.virtual_viewport() {
+            Some(viewport) => ([viewport.x, viewport.y], viewport.scale),
+            None => ([0.0, 0.0], self.pixel_scale()),
+        }
+    }
+
+    // The projection buffer may be in use, so the device has to be idle.
+    unsafe fn update_projection(&mut self) {
+        self.base.device_data.device.device_wait_idle().unwrap();
+
+        // TODO: Bundle all of this stuff together into a single
+        // uniform buffer struct that can be easily updated.
+        let (screen_width, screen_height) = (self.screen_width(), self.screen_height());
+        mat4::orthographic_projection(
+            &mut self.projection_matrix,
+            mat4::OrthographicProjectionInfo {
+                left: 0.0,
+                right: screen_width,
+                bottom: 0.0,
+                top: screen_height,
+                z_near: -mat4::Z_VIEW_DISTANCE,
+                z_far: mat4::Z_VIEW_DISTANCE,
+            },
+        );
+        self.projection_matrix_buffer
+            .set_data(&self.projection_matrix);
+
+        let (window_width, window_height) = self.window_size();
+        mat4::orthographic_projection(
+            &mut self.window_projection_matrix,
+            mat4::OrthographicProjectionInfo {
+                left: 0.0,
+                right: window_width,
+                bottom: 0.0,
+                top: window_height,
+                z_near: -mat4::Z_VIEW_DISTANCE,
+                z_far: mat4::Z_VIEW_DISTANCE,
+            },
+        );
+        self.window_projection_matrix_buffer
+            .set_data(&self.window_projection_matrix);
+    }
+}
+
 impl Graphics {
     pub unsafe fn new(title: &str) -> Self {
         let event_loop = EventLoop::new();
@@ -62,7 +407,6 @@ impl Graphics {
             base.device_data.clone(),
             &base.surface_data,
             &base.swapchain_data,
-            &window,
         );
 
         let viewports = [vk::Viewport {
@@ -88,15 +432,39 @@ impl Graphics {
             range: mem::size_of_val(&projection_matrix) as u64,
         };
 
+        let window_projection_matrix_buffer = buffer::Buffer::new(
+            &projection_matrix,
+            base.device_data.clone(),
+            vk::BufferUsageFlags::UNIFORM_BUFFER,
+        );
+        let window_projection_matrix_buffer_descriptor = vk::DescriptorBufferInfo {
+            buffer: window_projection_matrix_buffer.vk_buffer(),
+            ..projection_matrix_buffer_descriptor
+        };
+
         Self {
             resources: Resources {
                 render_pass,
                 viewports,
                 scissors,
+                frame: 0,
 
                 projection_matrix,
                 projection_matrix_buffer,
                 projection_matrix_buffer_descriptor,
+                window_projection_matrix: projection_matrix,
+                window_projection_matrix_buffer,
+                window_projection_matrix_buffer_descriptor,
+
+                scale_factor: window.scale_factor() as f32,
+                physical_pixels: false,
+                window: window::WindowControl::new(&window, title),
+
+                virtual_screen: None,
+                post_process: post_process::PostProcess::new(),
+
+                #[cfg(feature = "shader-compiler")]
+                shader_watcher: shader_compiler::ShaderWatcher::new(),
 
                 base,
             },
@@ -106,6 +474,66 @@ impl Graphics {
 
             needs_resize: true,
             needs_first_draw: true,
+
+            recorder: None,
+            replay: None,
+            gamepad_backend: None,
+
+            cursor_sprite: None,
+        }
+    }
+
+    // Sprites and the mouse are measured in logical pixels by default, which are
+    // bigger than physical ones on HiDPI screens so everything appears the same size.
+    pub fn set_physical_pixels(&mut self, physical_pixels: bool) {
+        self.resources.physical_pixels = physical_pixels;
+    }
+
+    pub fn set_virtual_resolution(
+        &mut self,
+        width: u32,
+        height: u32,
+        scaling: virtual_resolution::Scaling,
+    ) {
+        self.resources
+            .set_virtual_resolution(width, height, scaling);
+    }
+
+    // Records every input event and frame time to a file while the app runs.
+    pub fn record(&mut self, path: &str) {
+        self.recorder = Some(recording::Recorder::new(path));
+    }
+
+    // Feeds a recording to the app in place of input from the window, along
+    // with the recorded frame times. Live input resumes once it runs out.
+    pub fn replay(&mut self, path: &str) {
+        self.replay = Some(recording::Replay::load(path));
+    }
+
+    // Where the app's gamepads come from in place of the real ones, eg: VirtualGamepads.
+    pub fn set_gamepad_backend(&mut self, gamepad_backend: Box<dyn GamepadBackend>) {
+        self.gamepad_backend = Some(gamepad_backend);
+    }
+
+    fn update_cursor_sprite(&mut self, input: &Input) {
+        let window = &self.resources.window;
+        let image = match window.cursor_image() {
+            Some(image) if window.is_cursor_visible() => image,
+            _ => {
+                self.cursor_sprite = None;
+                return;
+            }
+        };
+
+        // The cursor is drawn over the whole window rather than the virtual screen, so the
+        // mouse is taken back out of it.
+        let (offset, scale) = self.resources.screen_transform();
+        let pixel_scale = self.resources.pixel_scale();
+        let x = (offset[0] + input.mouse_x() * scale) / pixel_scale;
+        let y = (offset[1] + input.mouse_y() * scale) / pixel_scale;
+        match &mut self.cursor_sprite {
+            Some(cursor_sprite) if cursor_sprite.is_for(image) => cursor_sprite.update(image, x, y),
+            _ => self.cursor_sprite = Some(window::CursorSprite::new(&self.resources, image, x, y)),
         }
     }
 
@@ -116,11 +544,74 @@ impl Graphics {
         let mut app = T::new(&mut self.resources);
         let mut now = time::Instant::now();
 
-        VkBase::render_loop(&window, &mut event_loop, |input| {
-            let delta_time = now.elapsed().as_secs_f32();
+        let mut input = match self.gamepad_backend.take() {
+            Some(gamepad_backend) => Input::with_gamepad_backend(gamepad_backend),
+            None => Input::new(),
+        };
+        input.set_scale_factor(self.resources.scale_factor);
+        input.set_physical_pixels(self.resources.physical_pixels);
+        if self.recorder.is_some() {
+            input.start_recording();
+        }
+        if self.replay.is_some() {
+            input.set_live(false);
+        }
+
+        VkBase::render_loop(&window, &mut event_loop, input, |input| {
+            let mut delta_time = now.elapsed().as_secs_f32();
             now = time::Instant::now();
 
+            let mut replayed_events = Vec::new();
+            if let Some(replay) = &mut self.replay {
+                match replay.next_frame() {
+                    Some(frame) => {
+                        delta_time = frame.delta_time;
+                        replayed_events = frame.events.clone();
+                    }
+                    None => {
+                        self.replay = None;
+                        input.set_live(true);
+                    }
+                }
+            }
+            for recorded_event in &replayed_events {
+                input.process_event_at(recorded_event.event.clone(), recorded_event.offset);
+            }
+            input.advance_time(delta_time);
+
+            self.resources.window.refresh(&window);
+
+            let scale_factor = window.scale_factor() as f32;
+            if scale_factor != self.resources.scale_factor {
+                self.resources.scale_factor = scale_factor;
+                self.needs_resize = true;
+                app.scale_factor_changed(&mut self.resources, scale_factor);
+            }
+
+            #[cfg(feature = "shader-compiler")]
+            if self.resources.shader_watcher.poll() {
+                self.resources
+                    .post_process
+                    .reload_shaders(&self.resources.shader_watcher);
+            }
+
             app.update(&mut self.resources, input, delta_time);
+            let (screen_offset, screen_scale) = self.resources.screen_transform();
+            self.resources
+                .window
+                .apply(&window, screen_offset, screen_scale);
+
+            // The frame is recorded before exiting, so that the input that exited isn't lost.
+            if let Some(recorder) = &mut self.recorder {
+                let mut events = replayed_events;
+                events.extend(input.take_recorded_events().unwrap_or_default());
+                recorder.record(&recording::RecordedFrame { delta_time, events });
+            }
+
+            if self.resources.window.is_exit_requested() {
+                return false;
+            }
+            self.update_cursor_sprite(input);
 
             if self.needs_resize {
                 self.needs_resize = false;
@@ -135,28 +626,33 @@ impl Graphics {
                 self.resources
                     .base
                     .resize(window_size.width, window_size.height);
-                self.resources
-                    .render_pass
-                    .resize(&window, &self.resources.base.swapchain_data);
-
-                // TODO: Bundle all of this stuff together into a single
-                // uniform buffer struct that can be easily updated.
-                mat4::orthographic_projection(
-                    &mut self.resources.projection_matrix,
-                    mat4::OrthographicProjectionInfo {
-                        left: 0.0,
-                        right: self.resources.base.surface_data.resolution.width as f32,
-                        bottom: 0.0,
-                        top: self.resources.base.surface_data.resolution.height as f32,
-                        z_near: -mat4::Z_VIEW_DISTANCE,
-                        z_far: mat4::Z_VIEW_DISTANCE,
-                    },
+                self.resources.render_pass.resize(
+                    &self.resources.base.surface_data,
+                    &self.resources.base.swapchain_data,
                 );
-                self.resources
-                    .projection_matrix_buffer
-                    .set_data(&self.resources.projection_matrix);
+
+                self.resources.update_projection();
             }
 
+            // Takes effect from the next frame's input.
+            input.set_virtual_viewport(self.resources.virtual_viewport());
+
+            // The post processing targets are the size the app draws at.
+            let (scene_width, scene_height) = match &self.resources.virtual_screen {
+                Some(virtual_screen) => (virtual_screen.width(), virtual_screen.height()),
+                None => (
+                    self.resources.base.surface_data.resolution.width,
+                    self.resources.base.surface_data.resolution.height,
+                ),
+            };
+            let needs_scene = self.resources.virtual_screen.is_none();
+            let mut post_process = mem::replace(
+                &mut self.resources.post_process,
+                post_process::PostProcess::new(),
+            );
+            post_process.prepare(&self.resources, scene_width, scene_height, needs_scene);
+            self.resources.post_process = post_process;
+
             let (present_index, _) = match self
                 .resources
                 .base
@@ -171,7 +667,7 @@ impl Graphics {
                 Ok(values) => values,
                 Err(_) => {
                     self.needs_resize = true;
-                    return;
+                    return true;
                 }
             };
 
@@ -198,6 +694,7 @@ impl Graphics {
             self.resources.scissors[0].extent.height =
                 self.resources.base.surface_data.resolution.height;
 
+            self.resources.frame += 1;
             self.resources.base.device_data.record_submit(
                 self.resources.base.command_data.draw_buffer,
                 self.resources.base.sync_data.draw_commands_reuse_fence,
@@ -205,21 +702,136 @@ impl Graphics {
                 &[self.resources.base.sync_data.present_complete_semaphore],
                 &[self.resources.base.sync_data.rendering_complete_semaphore],
                 |device, command_buffer| {
-                    self.resources.render_pass.begin(
+                    let compute = Compute {
                         device,
                         command_buffer,
-                        &self.resources.base.surface_data,
-                        present_index,
-                        &clear_values,
-                    );
+                        resources: &self.resources,
+                        dispatched: cell::Cell::new(false),
+                    };
+
+                    app.compute(&compute);
+                    compute.finish();
 
-                    let draw = Draw {
+                    let offscreen = Offscreen {
                         device,
                         command_buffer,
                         resources: &self.resources,
                     };
 
-                    app.draw(&draw);
+                    app.draw_offscreen(&offscreen);
+
+                    // The app draws into the virtual screen or the post processing scene when
+                    // there is one, using the screen's projection.
+                    let scene_target = match &self.resources.virtual_screen {
+                        Some(virtual_screen) => Some(virtual_screen.render_target()),
+                        None => self.resources.post_process.scene_target(),
+                    };
+                    let output = scene_target.map(|scene_target| {
+                        offscreen.render_with_projection(
+                            scene_target,
+                            Some(color::Color::TRANSPARENT),
+                            self.resources.projection_matrix_buffer_descriptor,
+                            [
+                                self.resources.screen_width(),
+                                self.resources.screen_height(),
+                            ],
+                            |draw| app.draw(draw),
+                        );
+
+                        self.resources
+                            .post_process
+                            .record(&offscreen, scene_target.texture())
+                    });
+
+                    // The virtual screen is drawn to the window with the same projection by
+                    // fitting the viewport around it.
+                    let screen_draw = match &self.resources.virtual_screen {
+                        Some(virtual_screen) => {
+                            let resolution = self.resources.base.surface_data.resolution;
+                            let viewport =
+                                virtual_screen.viewport(resolution.width, resolution.height);
+                            Draw {
+                                device,
+                                command_buffer,
+                                resources: &self.resources,
+                                viewports: [vk::Viewport {
+                                    x: viewport.x,
+                                    y: viewport.y,
+                                    width: viewport.width,
+                                    height: viewport.height,
+                                    min_depth: 0.0,
+                                    max_depth: 1.0,
+                                }],
+                                scissors: [vk::Rect2D {
+                                    offset: vk::Offset2D {
+                                        x: viewport.x as i32,
+                                        y: viewport.y as i32,
+                                    },
+                                    extent: vk::Extent2D {
+                                        width: viewport.width.ceil() as u32,
+                                        height: viewport.height.ceil() as u32,
+                                    },
+                                }],
+                                projection_matrix_buffer_descriptor: self
+                                    .resources
+                                    .projection_matrix_buffer_descriptor,
+                                view_width: self.resources.screen_width(),
+                                view_height: self.resources.screen_height(),
+                            }
+                        }
+                        None => Draw {
+                            device,
+                            command_buffer,
+                            resources: &self.resources,
+                            viewports: self.resources.viewports,
+                            scissors: self.resources.scissors,
+                            projection_matrix_buffer_descriptor: self
+                                .resources
+                                .projection_matrix_buffer_descriptor,
+                            view_width: self.resources.screen_width(),
+                            view_height: self.resources.screen_height(),
+                        },
+                    };
+
+                    self.resources.render_pass.begin(
+                        device,
+                        command_buffer,
+                        present_index,
+                        self.resources.base.surface_data.resolution,
+                        &clear_values,
+                    );
+
+                    match (&self.resources.virtual_screen, output) {
+                        (Some(virtual_screen), Some(output)) => {
+                            virtual_screen.draw(&screen_draw, &output)
+                        }
+                        (None, Some(output)) => {
+                            if let Some(present) = self.resources.post_process.present() {
+                                present.draw(
+                                    &screen_draw,
+                                    &output,
+                                    &output,
+                                    &post_process::pass::PassConstants::default(),
+                                );
+                            }
+                        }
+                        (_, None) => app.draw(&screen_draw),
+                    }
+                    if let Some(cursor_sprite) = &self.cursor_sprite {
+                        let (window_width, window_height) = self.resources.window_size();
+                        cursor_sprite.draw(&Draw {
+                            device,
+                            command_buffer,
+                            resources: &self.resources,
+                            viewports: self.resources.viewports,
+                            scissors: self.resources.scissors,
+                            projection_matrix_buffer_descriptor: self
+                                .resources
+                                .window_projection_matrix_buffer_descriptor,
+                            view_width: window_width,
+                            view_height: window_height,
+                        });
+                    }
 
                     self.resources.render_pass.end(device, command_buffer)
                 },
@@ -253,6 +865,8 @@ impl Graphics {
                 self.needs_first_draw = false;
                 window.set_visible(true);
             }
+
+            true
         });
     }
 }
@@ -0,0 +1,141 @@
+use crate::graphics::{color::Color, sprite_batch::Region, vec2::Vec2};
+
+// Each new particle picks a value uniformly between min and max.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RandomRange {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl RandomRange {
+    pub const fn new(min: f32, max: f32) -> Self {
+        Self { min, max }
+    }
+
+    pub const fn constant(value: f32) -> Self {
+        Self::new(value, value)
+    }
+}
+
+pub trait Lerp: Copy {
+    fn lerp(self, other: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for Color {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Color::lerp(self, other, t)
+    }
+}
+
+// A value over a particle's life, given as keys of (life from 0 to 1, value)
+// sorted by life and linearly interpolated between.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Curve<T: Lerp> {
+    pub keys: Vec<(f32, T)>,
+}
+
+impl<T: Lerp> Curve<T> {
+    pub fn new(keys: Vec<(f32, T)>) -> Self {
+        assert!(!keys.is_empty(), "Curves need at least one key");
+
+        Self { keys }
+    }
+
+    pub fn constant(value: T) -> Self {
+        Self::new(vec![(0.0, value)])
+    }
+
+    pub fn linear(start: T, end: T) -> Self {
+        Self::new(vec![(0.0, start), (1.0, end)])
+    }
+
+    pub fn sample(&self, life: f32) -> T {
+        let next = self.keys.iter().position(|&(key_life, _)| key_life > life);
+
+        match next {
+            Some(0) => self.keys[0].1,
+            Some(next) => {
+                let (start_life, start) = self.keys[next - 1];
+                let (end_life, end) = self.keys[next];
+                start.lerp(end, (life - start_life) / (end_life - start_life))
+            }
+            None => self.keys[self.keys.len() - 1].1,
+        }
+    }
+
+    // Samples the curve at evenly spaced points, from the start to the end of life.
+    pub fn bake(&self, count: usize) -> Vec<T> {
+        (0..count)
+            .map(|index| self.sample(index as f32 / (count.max(2) - 1) as f32))
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Burst {
+    // Seconds after the emitter starts.
+    pub time: f32,
+    pub count: u32,
+    // Fires again every interval seconds after the first time, if set.
+    pub interval: Option<f32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EmitterConfig {
+    // Particles per second.
+    pub spawn_rate: f32,
+    pub bursts: Vec<Burst>,
+    // Seconds the emitter keeps spawning for, bursts included, or forever if None.
+    pub duration: Option<f32>,
+    pub max_particles: usize,
+
+    // Particles spawn at a random point within this distance of the emitter.
+    pub spawn_radius: f32,
+    // In seconds.
+    pub lifetime: RandomRange,
+    // In pixels per second.
+    pub speed: RandomRange,
+    // Velocity points within spread radians either side of direction, 0 being right.
+    pub direction: f32,
+    pub spread: f32,
+
+    // In pixels per second squared.
+    pub gravity: Vec2,
+    // Fraction of velocity lost per second.
+    pub drag: f32,
+
+    pub color: Curve<Color>,
+    // Width and height in pixels.
+    pub size: Curve<f32>,
+    pub region: Region,
+}
+
+impl Default for EmitterConfig {
+    fn default() -> Self {
+        Self {
+            spawn_rate: 10.0,
+            bursts: Vec::new(),
+            duration: None,
+            max_particles: 1000,
+
+            spawn_radius: 0.0,
+            lifetime: RandomRange::constant(1.0),
+            speed: RandomRange::constant(50.0),
+            direction: -std::f32::consts::FRAC_PI_2,
+            spread: std::f32::consts::PI,
+
+            gravity: [0.0, 0.0],
+            drag: 0.0,
+
+            color: Curve::constant(Color::WHITE),
+            size: Curve::constant(8.0),
+            region: Region::FULL,
+        }
+    }
+}
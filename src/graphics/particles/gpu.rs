@@ -0,0 +1,385 @@
+use std::{mem, rc};
+
+use ash::vk;
+
+use super::{emitter::*, Blend, Emitter};
+use crate::graphics::{compute::*, texture, vk_resources::*, Compute, Draw, Resources};
+
+const WORKGROUP_SIZE: u32 = 64;
+// Number of samples taken from the color and size curves, shaders interpolate between them.
+const CURVE_SAMPLES: usize = 16;
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct Particle {
+    position: [f32; 2],
+    velocity: [f32; 2],
+    age: f32,
+    lifetime: f32,
+    padding: [f32; 2],
+}
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct Instance {
+    color: [f32; 4],
+    position: [f32; 2],
+    size: f32,
+    padding: f32,
+}
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct Curves {
+    colors: [[f32; 4]; CURVE_SAMPLES],
+    sizes: [f32; CURVE_SAMPLES],
+}
+
+impl Curves {
+    fn new(config: &EmitterConfig) -> Self {
+        let mut curves = Self {
+            colors: [[0.0; 4]; CURVE_SAMPLES],
+            sizes: [0.0; CURVE_SAMPLES],
+        };
+
+        for (i, color) in config.color.bake(CURVE_SAMPLES).into_iter().enumerate() {
+            curves.colors[i] = color.to_array();
+        }
+        for (i, size) in config.size.bake(CURVE_SAMPLES).into_iter().enumerate() {
+            curves.sizes[i] = size;
+        }
+
+        curves
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+#[repr(C)]
+struct SimulateConstants {
+    emitter_position: [f32; 2],
+    gravity: [f32; 2],
+    lifetime: [f32; 2],
+    speed: [f32; 2],
+    direction: f32,
+    spread: f32,
+    spawn_radius: f32,
+    drag: f32,
+    delta_time: f32,
+    spawn_start: u32,
+    spawn_count: u32,
+    max_particles: u32,
+    seed: u32,
+}
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct DrawConstants {
+    region: [f32; 4],
+    z: f32,
+}
+
+// Simulates a single emitter's particles in a compute shader and draws the ones
+// alive with an indirect draw, so their count never has to be read back.
+// Unlike the CPU path, spawning into a full buffer replaces the oldest particles.
+pub struct GpuParticleSystem {
+    emitter: Emitter,
+    // The config the curves buffer was last filled from, and new curves to write with the
+    // next dispatch once it changed.
+    uploaded_config: EmitterConfig,
+    pending_curves: Option<Curves>,
+    constants: SimulateConstants,
+    next_spawn_start: u32,
+    // Set by each update so a frame's particles are only simulated and counted once.
+    needs_dispatch: bool,
+
+    particles: StorageBuffer,
+    instances: StorageBuffer,
+    draw_command: StorageBuffer,
+    curves: StorageBuffer,
+
+    compute_pipeline: ComputePipeline,
+    pipeline: pipeline::Pipeline,
+
+    texture: rc::Rc<texture::Texture>,
+}
+
+impl GpuParticleSystem {
+    pub fn new(
+        resources: &Resources,
+        texture: rc::Rc<texture::Texture>,
+        blend: Blend,
+        config: EmitterConfig,
+        x: f32,
+        y: f32,
+        z: f32,
+    ) -> Self {
+        let max_particles = config.max_particles.max(1);
+
+        let particles = StorageBuffer::new(
+            resources,
+            &vec![
+                Particle {
+                    position: [0.0; 2],
+                    velocity: [0.0; 2],
+                    age: 0.0,
+                    lifetime: 0.0,
+                    padding: [0.0; 2],
+                };
+                max_particles
+            ],
+        );
+        let instances = StorageBuffer::new(
+            resources,
+            &vec![
+                Instance {
+                    color: [0.0; 4],
+                    position: [0.0; 2],
+                    size: 0.0,
+                    padding: 0.0,
+                };
+                max_particles
+            ],
+        );
+        let draw_command = StorageBuffer::new(resources, &[Self::empty_draw_command()]);
+        let curves = StorageBuffer::new(resources, &[Curves::new(&config)]);
+
+        let compute_pipeline = ComputePipeline::new(
+            resources,
+            include_bytes!("../../../shader/gpu_particle.comp.spv"),
+            &[
+                BindingType::StorageBuffer,
+                BindingType::StorageBuffer,
+                BindingType::StorageBuffer,
+                BindingType::StorageBuffer,
+            ],
+            mem::size_of::<SimulateConstants>() as u32,
+        );
+
+        let descriptor_layout_bindings = [
+            vk::DescriptorSetLayoutBinding {
+                binding: 1,
+                descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::FRAGMENT,
+                ..Default::default()
+            },
+            vk::DescriptorSetLayoutBinding {
+                binding: 2,
+                descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::VERTEX,
+                ..Default::default()
+            },
+        ];
+
+        // Instances are read once per quad instead of once per vertex.
+        let vertex_input_binding_descriptions = [vk::VertexInputBindingDescription {
+            binding: 0,
+            stride: mem::size_of::<Instance>() as u32,
+            input_rate: vk::VertexInputRate::INSTANCE,
+        }];
+        let vertex_input_attribute_descriptions = [
+            vk::VertexInputAttributeDescription {
+                location: 0,
+                binding: 0,
+                format: vk::Format::R32G32B32A32_SFLOAT,
+                offset: crate::offset_of!(Instance, color) as u32,
+            },
+            vk::VertexInputAttributeDescription {
+                location: 1,
+                binding: 0,
+                format: vk::Format::R32G32_SFLOAT,
+                offset: crate::offset_of!(Instance, position) as u32,
+            },
+            vk::VertexInputAttributeDescription {
+                location: 2,
+                binding: 0,
+                format: vk::Format::R32_SFLOAT,
+                offset: crate::offset_of!(Instance, size) as u32,
+            },
+        ];
+
+        let pipeline = unsafe {
+            pipeline::Pipeline::new(
+                resources,
+                pipeline::PipelineInfo {
+                    vertex_spv: include_bytes!("../../../shader/gpu_particle.vert.spv"),
+                    fragment_spv: include_bytes!("../../../shader/particle.frag.spv"),
+                    descriptor_layout_bindings: &descriptor_layout_bindings,
+                    vertex_input_binding_descriptions: &vertex_input_binding_descriptions,
+                    vertex_input_attribute_descriptions: &vertex_input_attribute_descriptions,
+                    push_constant_ranges: &[vk::PushConstantRange {
+                        stage_flags: vk::ShaderStageFlags::VERTEX,
+                        offset: 0,
+                        size: mem::size_of::<DrawConstants>() as u32,
+                    }],
+                    blend: match blend {
+                        Blend::Alpha => pipeline::Blend::Alpha,
+                        Blend::Additive => pipeline::Blend::Additive,
+                    },
+                    depth_write: false,
+                },
+            )
+        };
+
+        Self {
+            emitter: Emitter::new(config.clone(), x, y, z),
+            uploaded_config: config,
+            pending_curves: None,
+            constants: SimulateConstants::default(),
+            next_spawn_start: 0,
+            needs_dispatch: false,
+
+            particles,
+            instances,
+            draw_command,
+            curves,
+
+            compute_pipeline,
+            pipeline,
+
+            texture,
+        }
+    }
+
+    fn empty_draw_command() -> vk::DrawIndirectCommand {
+        vk::DrawIndirectCommand {
+            vertex_count: 6,
+            instance_count: 0,
+            first_vertex: 0,
+            first_instance: 0,
+        }
+    }
+
+    pub fn emitter(&self) -> &Emitter {
+        &self.emitter
+    }
+
+    // The emitter's particle count and whether it's finished only account for
+    // spawning, since the particles themselves only exist on the GPU.
+    pub fn emitter_mut(&mut self) -> &mut Emitter {
+        &mut self.emitter
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        let config = &self.emitter.config;
+        let max_particles = self.uploaded_config.max_particles.max(1);
+        assert_eq!(
+            config.max_particles.max(1),
+            max_particles,
+            "A GPU particle system's max particles can't change after it is created"
+        );
+
+        if *config != self.uploaded_config {
+            self.pending_curves = Some(Curves::new(config));
+            self.uploaded_config = config.clone();
+        }
+
+        let spawn_count = (self.emitter.spawn_count(delta_time) as usize).min(max_particles);
+        let config = &self.emitter.config;
+
+        self.constants = SimulateConstants {
+            emitter_position: [self.emitter.x, self.emitter.y],
+            gravity: config.gravity,
+            lifetime: [config.lifetime.min, config.lifetime.max],
+            speed: [config.speed.min, config.speed.max],
+            direction: config.direction,
+            spread: config.spread,
+            spawn_radius: config.spawn_radius,
+            drag: config.drag,
+            delta_time,
+            spawn_start: self.next_spawn_start,
+            spawn_count: spawn_count as u32,
+            max_particles: max_particles as u32,
+            seed: self.constants.seed.wrapping_add(1),
+        };
+
+        self.next_spawn_start = (self.next_spawn_start + spawn_count as u32) % max_particles as u32;
+        self.needs_dispatch = true;
+    }
+
+    pub fn compute(&mut self, compute: &Compute) {
+        if !self.needs_dispatch {
+            return;
+        }
+        self.needs_dispatch = false;
+
+        if let Some(curves) = self.pending_curves.take() {
+            self.curves.update(compute, &[curves]);
+        }
+        // The compute shader counts up the instances alive this frame.
+        self.draw_command
+            .update(compute, &[Self::empty_draw_command()]);
+
+        self.compute_pipeline.dispatch(
+            compute,
+            &[
+                Binding::StorageBuffer(&self.particles),
+                Binding::StorageBuffer(&self.instances),
+                Binding::StorageBuffer(&self.draw_command),
+                Binding::StorageBuffer(&self.curves),
+            ],
+            &self.constants,
+            [self.constants.max_particles.div_ceil(WORKGROUP_SIZE), 1, 1],
+        );
+    }
+
+    pub fn draw(&self, draw: &Draw) {
+        let region = self.emitter.config.region;
+        let constants = DrawConstants {
+            region: [region.x, region.y, region.width, region.height],
+            z: self.emitter.z,
+        };
+
+        unsafe {
+            let descriptor_set = self.pipeline.descriptor_set(draw);
+            let write_descriptor_sets = [
+                vk::WriteDescriptorSet {
+                    dst_set: descriptor_set,
+                    dst_binding: 1,
+                    descriptor_count: 1,
+                    descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                    p_image_info: &self.texture.descriptor,
+                    ..Default::default()
+                },
+                vk::WriteDescriptorSet {
+                    dst_set: descriptor_set,
+                    dst_binding: 2,
+                    descriptor_count: 1,
+                    descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+                    p_buffer_info: &draw.projection_matrix_buffer_descriptor,
+                    ..Default::default()
+                },
+            ];
+
+            draw.device
+                .update_descriptor_sets(&write_descriptor_sets, &[]);
+
+            self.pipeline.bind(draw, descriptor_set);
+
+            draw.device.cmd_push_constants(
+                draw.command_buffer,
+                self.pipeline.layout(),
+                vk::ShaderStageFlags::VERTEX,
+                0,
+                std::slice::from_raw_parts(
+                    &constants as *const DrawConstants as *const u8,
+                    mem::size_of::<DrawConstants>(),
+                ),
+            );
+            draw.device.cmd_bind_vertex_buffers(
+                draw.command_buffer,
+                0,
+                &[self.instances.vk_buffer()],
+                &[0],
+            );
+            draw.device.cmd_draw_indirect(
+                draw.command_buffer,
+                self.draw_command.vk_buffer(),
+                0,
+                1,
+                mem::size_of::<vk::DrawIndirectCommand>() as u32,
+            );
+        }
+    }
+}
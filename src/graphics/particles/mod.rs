@@ -0,0 +1,478 @@
+pub mod emitter;
+pub mod gpu;
+
+use std::{f32::consts::TAU, mem, rc};
+
+use ash::vk;
+
+use super::{texture, vec2, vk_base::*, vk_resources::*, Draw, Resources};
+use emitter::*;
+
+// Intervals shorter than this are stretched so a single update can't spin forever.
+const MIN_BURST_INTERVAL: f32 = 0.001;
+const RANDOM_SEED: u32 = 0x9e3779b9;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Blend {
+    Alpha,
+    Additive,
+}
+
+#[derive(Clone, Debug, Copy)]
+pub struct Vertex {
+    pos: [f32; 3],
+    uv: [f32; 2],
+    color: [f32; 4],
+}
+
+impl Vertex {
+    pub fn get_info() -> (
+        [vk::VertexInputBindingDescription; 1],
+        [vk::VertexInputAttributeDescription; 3],
+    ) {
+        let vertex_input_binding_descriptions = [vk::VertexInputBindingDescription {
+            binding: 0,
+            stride: mem::size_of::<Vertex>() as u32,
+            input_rate: vk::VertexInputRate::VERTEX,
+        }];
+        let vertex_input_attribute_descriptions = [
+            vk::VertexInputAttributeDescription {
+                location: 0,
+                binding: 0,
+                format: vk::Format::R32G32B32_SFLOAT,
+                offset: crate::offset_of!(Vertex, pos) as u32,
+            },
+            vk::VertexInputAttributeDescription {
+                location: 1,
+                binding: 0,
+                format: vk::Format::R32G32_SFLOAT,
+                offset: crate::offset_of!(Vertex, uv) as u32,
+            },
+            vk::VertexInputAttributeDescription {
+                location: 2,
+                binding: 0,
+                format: vk::Format::R32G32B32A32_SFLOAT,
+                offset: crate::offset_of!(Vertex, color) as u32,
+            },
+        ];
+
+        (
+            vertex_input_binding_descriptions,
+            vertex_input_attribute_descriptions,
+        )
+    }
+}
+
+// Xorshift, plenty for scattering particles and the same every run.
+struct Random {
+    state: u32,
+}
+
+impl Random {
+    fn next(&mut self) -> f32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+
+        (self.state >> 8) as f32 / (1 << 24) as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next()
+    }
+}
+
+struct Particle {
+    position: vec2::Vec2,
+    velocity: vec2::Vec2,
+    age: f32,
+    lifetime: f32,
+}
+
+pub struct Emitter {
+    pub config: EmitterConfig,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    // Inactive emitters stop spawning but their particles live out their lives.
+    pub active: bool,
+
+    time: f32,
+    spawn_accumulator: f32,
+    next_bursts: Vec<Option<f32>>,
+    pending: u32,
+    particles: Vec<Particle>,
+}
+
+impl Emitter {
+    fn new(config: EmitterConfig, x: f32, y: f32, z: f32) -> Self {
+        let next_bursts = config.bursts.iter().map(|burst| Some(burst.time)).collect();
+
+        Self {
+            config,
+            x,
+            y,
+            z,
+            active: true,
+
+            time: 0.0,
+            spawn_accumulator: 0.0,
+            next_bursts,
+            pending: 0,
+            particles: Vec::new(),
+        }
+    }
+
+    // Starts spawning from the beginning again, including bursts.
+    pub fn restart(&mut self) {
+        self.active = true;
+        self.time = 0.0;
+        self.spawn_accumulator = 0.0;
+        self.next_bursts = self
+            .config
+            .bursts
+            .iter()
+            .map(|burst| Some(burst.time))
+            .collect();
+    }
+
+    // Spawns particles on the next update, regardless of the spawn rate.
+    pub fn emit(&mut self, count: u32) {
+        self.pending += count;
+    }
+
+    pub fn particle_count(&self) -> usize {
+        self.particles.len()
+    }
+
+    // Done spawning and every particle has died.
+    pub fn is_finished(&self) -> bool {
+        let spawning = self.active
+            && (self
+                .config
+                .duration
+                .is_none_or(|duration| self.time < duration)
+                || self.next_bursts.iter().any(Option::is_some));
+
+        !spawning && self.pending == 0 && self.particles.is_empty()
+    }
+
+    fn simulate(&mut self, delta_time: f32) {
+        let gravity = vec2::scale(self.config.gravity, delta_time);
+        let drag = (1.0 - self.config.drag * delta_time).max(0.0);
+
+        for particle in &mut self.particles {
+            particle.age += delta_time;
+            particle.velocity = vec2::scale(vec2::add(particle.velocity, gravity), drag);
+            particle.position = vec2::add(
+                particle.position,
+                vec2::scale(particle.velocity, delta_time),
+            );
+        }
+
+        self.particles
+            .retain(|particle| particle.age < particle.lifetime);
+    }
+
+    fn spawn_count(&mut self, delta_time: f32) -> u32 {
+        let mut count = mem::take(&mut self.pending);
+
+        if !self.active {
+            return count;
+        }
+
+        let start = self.time;
+        self.time += delta_time;
+
+        let end = match self.config.duration {
+            Some(duration) => self.time.min(duration),
+            None => self.time,
+        };
+        if end > start {
+            self.spawn_accumulator += self.config.spawn_rate * (end - start);
+            let spawned = self.spawn_accumulator.floor();
+            self.spawn_accumulator -= spawned;
+            count += spawned as u32;
+        }
+
+        // Bursts that would fire once the duration is over never do, so a repeating burst
+        // stops along with the rest of the spawning.
+        let duration = self.config.duration.unwrap_or(f32::INFINITY);
+        for (burst, next) in self.config.bursts.iter().zip(&mut self.next_bursts) {
+            while let Some(time) = *next {
+                if time >= duration {
+                    *next = None;
+                    break;
+                }
+                if time >= self.time {
+                    break;
+                }
+
+                count += burst.count;
+                *next = burst
+                    .interval
+                    .map(|interval| time + interval.max(MIN_BURST_INTERVAL));
+            }
+        }
+
+        count
+    }
+
+    fn spawn(&mut self, random: &mut Random) {
+        let config = &self.config;
+
+        let angle = config.direction + random.range(-config.spread, config.spread);
+        let speed = random.range(config.speed.min, config.speed.max);
+
+        // Taking the square root spreads particles evenly over the circle's area.
+        let offset_angle = random.range(0.0, TAU);
+        let offset_distance = config.spawn_radius * random.next().sqrt();
+
+        self.particles.push(Particle {
+            position: [
+                self.x + offset_angle.cos() * offset_distance,
+                self.y + offset_angle.sin() * offset_distance,
+            ],
+            velocity: [angle.cos() * speed, angle.sin() * speed],
+            age: 0.0,
+            lifetime: random.range(config.lifetime.min, config.lifetime.max),
+        });
+    }
+}
+
+pub struct ParticleSystem {
+    device_data: rc::Rc<device_data::DeviceData>,
+
+    emitters: Vec<Emitter>,
+    random: Random,
+
+    index_buffer: Option<buffer::Buffer>,
+    vertex_buffer: Option<buffer::Buffer>,
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+
+    pipeline: pipeline::Pipeline,
+
+    texture: rc::Rc<texture::Texture>,
+}
+
+impl ParticleSystem {
+    pub fn new(resources: &Resources, texture: rc::Rc<texture::Texture>, blend: Blend) -> Self {
+        let descriptor_layout_bindings = [
+            vk::DescriptorSetLayoutBinding {
+                binding: 1,
+                descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::FRAGMENT,
+                ..Default::default()
+            },
+            vk::DescriptorSetLayoutBinding {
+                binding: 2,
+                descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::VERTEX,
+                ..Default::default()
+            },
+        ];
+
+        let (vertex_input_binding_descriptions, vertex_input_attribute_descriptions) =
+            Vertex::get_info();
+
+        let pipeline = unsafe {
+            pipeline::Pipeline::new(
+                resources,
+                pipeline::PipelineInfo {
+                    vertex_spv: include_bytes!("../../../shader/particle.vert.spv"),
+                    fragment_spv: include_bytes!("../../../shader/particle.frag.spv"),
+                    descriptor_layout_bindings: &descriptor_layout_bindings,
+                    vertex_input_binding_descriptions: &vertex_input_binding_descriptions,
+                    vertex_input_attribute_descriptions: &vertex_input_attribute_descriptions,
+                    push_constant_ranges: &[],
+                    blend: match blend {
+                        Blend::Alpha => pipeline::Blend::Alpha,
+                        Blend::Additive => pipeline::Blend::Additive,
+                    },
+                    depth_write: false,
+                },
+            )
+        };
+
+        Self {
+            device_data: resources.base.device_data.clone(),
+
+            emitters: Vec::new(),
+            random: Random { state: RANDOM_SEED },
+
+            index_buffer: None,
+            vertex_buffer: None,
+            vertices: Vec::new(),
+            indices: Vec::new(),
+
+            pipeline,
+
+            texture,
+        }
+    }
+
+    pub fn add_emitter(&mut self, config: EmitterConfig, x: f32, y: f32, z: f32) -> usize {
+        self.emitters.push(Emitter::new(config, x, y, z));
+        self.emitters.len() - 1
+    }
+
+    pub fn emitter(&self, index: usize) -> &Emitter {
+        &self.emitters[index]
+    }
+
+    pub fn emitter_mut(&mut self, index: usize) -> &mut Emitter {
+        &mut self.emitters[index]
+    }
+
+    pub fn particle_count(&self) -> usize {
+        self.emitters.iter().map(Emitter::particle_count).sum()
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        for emitter in &mut self.emitters {
+            emitter.simulate(delta_time);
+
+            let count = emitter.spawn_count(delta_time);
+            let count = (count as usize).min(
+                emitter
+                    .config
+                    .max_particles
+                    .saturating_sub(emitter.particles.len()),
+            );
+            for _ in 0..count {
+                emitter.spawn(&mut self.random);
+            }
+        }
+
+        self.batch();
+    }
+
+    fn batch(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+
+        for emitter in &self.emitters {
+            let region = emitter.config.region;
+
+            for particle in &emitter.particles {
+                let life = particle.age / particle.lifetime;
+                let half_size = emitter.config.size.sample(life) * 0.5;
+                let color = emitter.config.color.sample(life).to_array();
+                let [x, y] = particle.position;
+
+                let vertex_count = self.vertices.len() as u32;
+
+                self.vertices.push(Vertex {
+                    pos: [x - half_size, y - half_size, emitter.z],
+                    uv: [region.x, region.y],
+                    color,
+                });
+                self.vertices.push(Vertex {
+                    pos: [x - half_size, y + half_size, emitter.z],
+                    uv: [region.x, region.y + region.height],
+                    color,
+                });
+                self.vertices.push(Vertex {
+                    pos: [x + half_size, y + half_size, emitter.z],
+                    uv: [region.x + region.width, region.y + region.height],
+                    color,
+                });
+                self.vertices.push(Vertex {
+                    pos: [x + half_size, y - half_size, emitter.z],
+                    uv: [region.x + region.width, region.y],
+                    color,
+                });
+
+                self.indices.push(vertex_count);
+                self.indices.push(vertex_count + 1);
+                self.indices.push(vertex_count + 2);
+                self.indices.push(vertex_count + 2);
+                self.indices.push(vertex_count + 3);
+                self.indices.push(vertex_count);
+            }
+        }
+
+        unsafe {
+            self.device_data.device.device_wait_idle().unwrap();
+
+            // Buffers can't be initialized with no data.
+            if self.indices.is_empty() || self.vertices.is_empty() {
+                self.index_buffer = None;
+                self.vertex_buffer = None;
+                return;
+            }
+
+            self.index_buffer = Some(buffer::Buffer::new(
+                &self.indices,
+                self.device_data.clone(),
+                vk::BufferUsageFlags::INDEX_BUFFER,
+            ));
+
+            self.vertex_buffer = Some(buffer::Buffer::new(
+                &self.vertices,
+                self.device_data.clone(),
+                vk::BufferUsageFlags::VERTEX_BUFFER,
+            ));
+        }
+    }
+
+    pub fn draw(&self, draw: &Draw) {
+        if self.vertex_buffer.is_none() || self.index_buffer.is_none() {
+            return;
+        }
+
+        let vertex_buffer = self.vertex_buffer.as_ref().unwrap();
+        let index_buffer = self.index_buffer.as_ref().unwrap();
+
+        unsafe {
+            let descriptor_set = self.pipeline.descriptor_set(draw);
+            let write_descriptor_sets = [
+                vk::WriteDescriptorSet {
+                    dst_set: descriptor_set,
+                    dst_binding: 1,
+                    descriptor_count: 1,
+                    descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                    p_image_info: &self.texture.descriptor,
+                    ..Default::default()
+                },
+                vk::WriteDescriptorSet {
+                    dst_set: descriptor_set,
+                    dst_binding: 2,
+                    descriptor_count: 1,
+                    descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+                    p_buffer_info: &draw.projection_matrix_buffer_descriptor,
+                    ..Default::default()
+                },
+            ];
+
+            draw.device
+                .update_descriptor_sets(&write_descriptor_sets, &[]);
+
+            self.pipeline.bind(draw, descriptor_set);
+
+            draw.device.cmd_bind_vertex_buffers(
+                draw.command_buffer,
+                0,
+                &[vertex_buffer.vk_buffer()],
+                &[0],
+            );
+            draw.device.cmd_bind_index_buffer(
+                draw.command_buffer,
+                index_buffer.vk_buffer(),
+                0,
+                vk::IndexType::UINT32,
+            );
+            draw.device.cmd_draw_indexed(
+                draw.command_buffer,
+                index_buffer.len() as u32,
+                1,
+                0,
+                0,
+                1,
+            );
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use lyon::{
+    math::{point, vector, Angle},
+    path::{builder::SvgPathBuilder, path::BuilderImpl, ArcFlags},
+    tessellation::{self, BuffersBuilder, FillVertex, StrokeVertex, VertexBuffers},
+};
+
+use super::{
+    shape_batch::{LineJoin, MITER_LIMIT},
+    vec2::Vec2,
+};
+
+// eg: a path with a NaN or infinite point.
+pub use lyon::tessellation::TessellationError;
+
+// Maximum distance in pixels between a curve and the line segments used to approximate it.
+const TOLERANCE: f32 = 0.1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FillRule {
+    NonZero,
+    EvenOdd,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineCap {
+    Butt,
+    Square,
+    Round,
+}
+
+pub struct PathStroke {
+    pub thickness: f32,
+    pub cap: LineCap,
+    pub join: LineJoin,
+}
+
+pub struct Mesh {
+    pub points: Vec<Vec2>,
+    pub indices: Vec<u32>,
+}
+
+#[derive(Clone)]
+pub struct Path {
+    path: lyon::path::Path,
+}
+
+impl Path {
+    pub fn builder() -> PathBuilder {
+        PathBuilder {
+            builder: lyon::path::Path::svg_builder(),
+        }
+    }
+
+    pub fn tessellate_fill(&self, fill_rule: FillRule) -> Result<Mesh, TessellationError> {
+        let fill_rule = match fill_rule {
+            FillRule::NonZero => tessellation::FillRule::NonZero,
+            FillRule::EvenOdd => tessellation::FillRule::EvenOdd,
+        };
+        let options = tessellation::FillOptions::tolerance(TOLERANCE).with_fill_rule(fill_rule);
+
+        let mut geometry: VertexBuffers<Vec2, u32> = VertexBuffers::new();
+        tessellation::FillTessellator::new().tessellate_path(
+            &self.path,
+            &options,
+            &mut BuffersBuilder::new(&mut geometry, |vertex: FillVertex| {
+                vertex.position().to_array()
+            }),
+        )?;
+
+        Ok(Mesh {
+            points: geometry.vertices,
+            indices: geometry.indices,
+        })
+    }
+
+    pub fn tessellate_stroke(&self, stroke: &PathStroke) -> Result<Mesh, TessellationError> {
+        let cap = match stroke.cap {
+            LineCap::Butt => tessellation::LineCap::Butt,
+            LineCap::Square => tessellation::LineCap::Square,
+            LineCap::Round => tessellation::LineCap::Round,
+        };
+        let join = match stroke.join {
+            LineJoin::Miter => tessellation::LineJoin::MiterClip,
+            LineJoin::Round => tessellation::LineJoin::Round,
+            LineJoin::Bevel => tessellation::LineJoin::Bevel,
+        };
+        let options = tessellation::StrokeOptions::tolerance(TOLERANCE)
+            .with_line_width(stroke.thickness)
+            .with_line_cap(cap)
+            .with_line_join(join)
+            .with_miter_limit(MITER_LIMIT);
+
+        let mut geometry: VertexBuffers<Vec2, u32> = VertexBuffers::new();
+        tessellation::StrokeTessellator::new().tessellate_path(
+            &self.path,
+            &options,
+            &mut BuffersBuilder::new(&mut geometry, |vertex: StrokeVertex| {
+                vertex.position().to_array()
+            }),
+        )?;
+
+        Ok(Mesh {
+            points: geometry.vertices,
+            indices: geometry.indices,
+        })
+    }
+}
+
+// Paths follow the same rules as SVG path data, eg: drawing without first
+// moving starts the path at the origin, and multiple sub-paths are allowed.
+pub struct PathBuilder {
+    builder: lyon::path::builder::WithSvg<BuilderImpl>,
+}
+
+impl PathBuilder {
+    pub fn move_to(&mut self, to: Vec2) {
+        self.builder.move_to(point(to[0], to[1]));
+    }
+
+    pub fn line_to(&mut self, to: Vec2) {
+        self.builder.line_to(point(to[0], to[1]));
+    }
+
+    pub fn quadratic_to(&mut self, control: Vec2, to: Vec2) {
+        self.builder
+            .quadratic_bezier_to(point(control[0], control[1]), point(to[0], to[1]));
+    }
+
+    pub fn cubic_to(&mut self, control_1: Vec2, control_2: Vec2, to: Vec2) {
+        self.builder.cubic_bezier_to(
+            point(control_1[0], control_1[1]),
+            point(control_2[0], control_2[1]),
+            point(to[0], to[1]),
+        );
+    }
+
+    // Draws an elliptical arc from the current position to the given point, like SVG's "A" command.
+    pub fn arc_to(&mut self, radii: Vec2, x_rotation: f32, large_arc: bool, sweep: bool, to: Vec2) {
+        self.builder.arc_to(
+            vector(radii[0], radii[1]),
+            Angle::radians(x_rotation),
+            ArcFlags { large_arc, sweep },
+            point(to[0], to[1]),
+        );
+    }
+
+    // Draws an elliptical arc around the given center, starting at the current position.
+    pub fn arc(&mut self, center: Vec2, radii: Vec2, sweep_angle: f32, x_rotation: f32) {
+        self.builder.arc(
+            point(center[0], center[1]),
+            vector(radii[0], radii[1]),
+            Angle::radians(sweep_angle),
+            Angle::radians(x_rotation),
+        );
+    }
+
+    pub fn close(&mut self) {
+        self.builder.close();
+    }
+
+    pub fn build(self) -> Path {
+        Path {
+            path: self.builder.build(),
+        }
+    }
+}
@@ -0,0 +1,458 @@
+pub(super) mod pass;
+
+use std::{fs, rc, time};
+
+use super::{render_target, texture, Offscreen, Resources};
+use pass::{Pass, PassConstants};
+
+// Bright areas are extracted and blurred at half the size of the screen.
+const BLOOM_DOWNSCALE: u32 = 2;
+
+#[derive(Clone)]
+pub enum Effect {
+    // Makes areas brighter than the threshold glow, the glow spreading by radius texels.
+    Bloom {
+        threshold: f32,
+        intensity: f32,
+        radius: f32,
+    },
+    // Darkens the screen from radius outwards, 0 being the center and 1 the corners.
+    Vignette {
+        intensity: f32,
+        radius: f32,
+        softness: f32,
+    },
+    // Replaces colors with ones looked up in a LUT, see neutral_lut for its layout.
+    ColorGrading {
+        lut: rc::Rc<texture::Texture>,
+        intensity: f32,
+    },
+    // Curves the screen like an old monitor's glass, darkening between rows of pixels.
+    Crt {
+        curvature: f32,
+        scanline_intensity: f32,
+    },
+    // Smooths jagged edges.
+    Fxaa,
+    Custom(CustomEffect),
+}
+
+// A fragment shader written against the same interface as the built-in effects:
+//
+//     layout (binding = 0) uniform sampler2D source;
+//     layout (binding = 1) uniform sampler2D extra;
+//     layout (push_constant) uniform PushConstants {
+//         vec2 texel_size;
+//         float time;
+//         float padding;
+//         vec4 params[4];
+//     } pc;
+//     layout (location = 0) in vec2 o_uv;
+//     layout (location = 0) out vec4 u_frag_color;
+//
+// Extra is the effect's texture, or the source again when it has none.
+#[derive(Clone)]
+pub struct CustomEffect {
+    pub fragment_spv: rc::Rc<[u8]>,
+    pub texture: Option<rc::Rc<texture::Texture>>,
+    pub params: [[f32; 4]; 4],
+}
+
+impl CustomEffect {
+    pub fn new(fragment_spv: &[u8]) -> Self {
+        Self {
+            fragment_spv: fragment_spv.into(),
+            texture: None,
+            params: [[0.0; 4]; 4],
+        }
+    }
+
+    pub fn from_file(path: &str) -> Self {
+        let fragment_spv =
+            fs::read(path).unwrap_or_else(|_| panic!("Failed to load file {}", path));
+
+        Self::new(&fragment_spv)
+    }
+}
+
+// A LUT that leaves colors as they are, to be saved and edited alongside a screenshot.
+// It's a strip of size squares, red increasing across each square, green down it and blue
+// from one square to the next.
+pub fn neutral_lut(resources: &Resources, size: u32) -> texture::Texture {
+    let mut image_data = Vec::with_capacity((size * size * size * 4) as usize);
+    let max = (size - 1) as f32;
+
+    for green in 0..size {
+        for blue in 0..size {
+            for red in 0..size {
+                image_data.extend_from_slice(&[
+                    (red as f32 / max * 255.0).round() as u8,
+                    (green as f32 / max * 255.0).round() as u8,
+                    (blue as f32 / max * 255.0).round() as u8,
+                    255,
+                ]);
+            }
+        }
+    }
+
+    texture::Texture::from_rgba(
+        resources,
+        size * size,
+        size,
+        &image_data,
+        texture::Filter::Linear,
+    )
+}
+
+// The built-in effects only use the first four params.
+fn first_params(values: [f32; 4]) -> [[f32; 4]; 4] {
+    [values, [0.0; 4], [0.0; 4], [0.0; 4]]
+}
+
+// Effects are rebuilt when their kind, or a custom effect's shader, changes.
+enum EffectKey {
+    Bloom,
+    Vignette,
+    ColorGrading,
+    Crt,
+    Fxaa,
+    Custom(rc::Rc<[u8]>),
+}
+
+impl EffectKey {
+    fn new(effect: &Effect) -> Self {
+        match effect {
+            Effect::Bloom { .. } => Self::Bloom,
+            Effect::Vignette { .. } => Self::Vignette,
+            Effect::ColorGrading { .. } => Self::ColorGrading,
+            Effect::Crt { .. } => Self::Crt,
+            Effect::Fxaa => Self::Fxaa,
+            Effect::Custom(custom) => Self::Custom(custom.fragment_spv.clone()),
+        }
+    }
+
+    fn matches(&self, effect: &Effect) -> bool {
+        match (self, effect) {
+            (Self::Custom(fragment_spv), Effect::Custom(custom)) => {
+                rc::Rc::ptr_eq(fragment_spv, &custom.fragment_spv)
+            }
+            (Self::Bloom, Effect::Bloom { .. })
+            | (Self::Vignette, Effect::Vignette { .. })
+            | (Self::ColorGrading, Effect::ColorGrading { .. })
+            | (Self::Crt, Effect::Crt { .. })
+            | (Self::Fxaa, Effect::Fxaa) => true,
+            _ => false,
+        }
+    }
+}
+
+// The blur is done in two passes, one for each direction.
+struct BloomPasses {
+    extract: Pass,
+    blur_horizontal: Pass,
+    blur_vertical: Pass,
+    combine: Pass,
+}
+
+enum EffectPasses {
+    Single(Pass),
+    Bloom(Box<BloomPasses>),
+}
+
+impl EffectPasses {
+    fn new(resources: &Resources, effect: &Effect) -> Self {
+        let single = |fragment_spv: &[u8]| {
+            Self::Single(Pass::new(resources, fragment_spv, texture::Filter::Linear))
+        };
+
+        match effect {
+            Effect::Bloom { .. } => Self::Bloom(Box::new(BloomPasses {
+                extract: Pass::new(
+                    resources,
+                    include_bytes!("../../../shader/bloom_extract.frag.spv"),
+                    texture::Filter::Linear,
+                ),
+                blur_horizontal: Pass::new(
+                    resources,
+                    include_bytes!("../../../shader/blur.frag.spv"),
+                    texture::Filter::Linear,
+                ),
+                blur_vertical: Pass::new(
+                    resources,
+                    include_bytes!("../../../shader/blur.frag.spv"),
+                    texture::Filter::Linear,
+                ),
+                combine: Pass::new(
+                    resources,
+                    include_bytes!("../../../shader/bloom_combine.frag.spv"),
+                    texture::Filter::Linear,
+                ),
+            })),
+            Effect::Vignette { .. } => single(include_bytes!("../../../shader/vignette.frag.spv")),
+            Effect::ColorGrading { .. } => {
+                single(include_bytes!("../../../shader/color_grading.frag.spv"))
+            }
+            Effect::Crt { .. } => single(include_bytes!("../../../shader/crt.frag.spv")),
+            Effect::Fxaa => single(include_bytes!("../../../shader/fxaa.frag.spv")),
+            Effect::Custom(custom) => single(&custom.fragment_spv),
+        }
+    }
+}
+
+struct Targets {
+    width: u32,
+    height: u32,
+    // What the app draws into, unless the virtual screen is used instead.
+    scene: Option<render_target::RenderTarget>,
+    ping_pong: [render_target::RenderTarget; 2],
+    bloom: Option<[render_target::RenderTarget; 2]>,
+}
+
+// Full screen passes run in order over what the app drew, between drawing and presenting.
+pub(super) struct PostProcess {
+    effects: Vec<Effect>,
+    built: Vec<(EffectKey, EffectPasses)>,
+    targets: Option<Targets>,
+    // Draws the result to the window when there's no virtual screen to do it.
+    present: Option<Pass>,
+    start: time::Instant,
+}
+
+impl PostProcess {
+    pub fn new() -> Self {
+        Self {
+            effects: Vec::new(),
+            built: Vec::new(),
+            targets: None,
+            present: None,
+            start: time::Instant::now(),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.effects.is_empty()
+    }
+
+    pub fn effects(&self) -> &[Effect] {
+        &self.effects
+    }
+
+    pub fn effects_mut(&mut self) -> &mut Vec<Effect> {
+        &mut self.effects
+    }
+
+    pub fn set_effects(&mut self, effects: Vec<Effect>) {
+        self.effects = effects;
+        self.start = time::Instant::now();
+    }
+
+    // Custom effects get the latest build of their shader, and are rebuilt in prepare.
+    #[cfg(feature = "shader-compiler")]
+    pub fn reload_shaders(&mut self, shader_watcher: &super::shader_compiler::ShaderWatcher) {
+        for effect in &mut self.effects {
+            if let Effect::Custom(custom) = effect {
+                if let Some(spv) = shader_watcher.latest(&custom.fragment_spv) {
+                    custom.fragment_spv = spv;
+                }
+            }
+        }
+    }
+
+    // Builds passes for changed effects, and targets of the size the app draws at.
+    pub fn prepare(&mut self, resources: &Resources, width: u32, height: u32, needs_scene: bool) {
+        if !self.is_active() {
+            self.built.clear();
+            self.targets = None;
+            self.present = None;
+            return;
+        }
+
+        self.built.truncate(self.effects.len());
+        for (index, effect) in self.effects.iter().enumerate() {
+            match self.built.get_mut(index) {
+                Some((key, _)) if key.matches(effect) => {}
+                Some(built) => {
+                    *built = (EffectKey::new(effect), EffectPasses::new(resources, effect))
+                }
+                None => self
+                    .built
+                    .push((EffectKey::new(effect), EffectPasses::new(resources, effect))),
+            }
+        }
+
+        let needs_bloom = self
+            .effects
+            .iter()
+            .any(|effect| matches!(effect, Effect::Bloom { .. }));
+        let targets_changed = self.targets.as_ref().is_none_or(|targets| {
+            targets.width != width
+                || targets.height != height
+                || targets.scene.is_some() != needs_scene
+                || targets.bloom.is_some() != needs_bloom
+        });
+        if targets_changed {
+            // Dropped first so both don't exist at once.
+            self.targets = None;
+
+            let new_target = |width: u32, height: u32| {
+                render_target::RenderTarget::new(
+                    resources,
+                    width.max(1),
+                    height.max(1),
+                    texture::Filter::Linear,
+                    false,
+                )
+            };
+            self.targets = Some(Targets {
+                width,
+                height,
+                scene: needs_scene.then(|| new_target(width, height)),
+                ping_pong: [new_target(width, height), new_target(width, height)],
+                bloom: needs_bloom.then(|| {
+                    [
+                        new_target(width / BLOOM_DOWNSCALE, height / BLOOM_DOWNSCALE),
+                        new_target(width / BLOOM_DOWNSCALE, height / BLOOM_DOWNSCALE),
+                    ]
+                }),
+            });
+        }
+
+        if needs_scene && self.present.is_none() {
+            self.present = Some(Pass::new(
+                resources,
+                include_bytes!("../../../shader/copy.frag.spv"),
+                texture::Filter::Nearest,
+            ));
+        }
+    }
+
+    pub fn scene_target(&self) -> Option<&render_target::RenderTarget> {
+        self.targets.as_ref()?.scene.as_ref()
+    }
+
+    pub fn present(&self) -> Option<&Pass> {
+        self.present.as_ref()
+    }
+
+    // Runs every effect over the scene, returning the texture holding the result.
+    pub fn record(
+        &self,
+        offscreen: &Offscreen,
+        scene: rc::Rc<texture::Texture>,
+    ) -> rc::Rc<texture::Texture> {
+        let Some(targets) = &self.targets else {
+            return scene;
+        };
+
+        let time = self.start.elapsed().as_secs_f32();
+        let constants = |source: &texture::Texture, params: [[f32; 4]; 4]| PassConstants {
+            texel_size: [1.0 / source.width() as f32, 1.0 / source.height() as f32],
+            time,
+            padding: 0.0,
+            params,
+        };
+
+        let mut current = scene;
+        let mut next = 0;
+
+        for (effect, (_, passes)) in self.effects.iter().zip(&self.built) {
+            let target = &targets.ping_pong[next];
+
+            match (effect, passes) {
+                (
+                    Effect::Bloom {
+                        threshold,
+                        intensity,
+                        radius,
+                    },
+                    EffectPasses::Bloom(bloom_passes),
+                ) => {
+                    let BloomPasses {
+                        extract,
+                        blur_horizontal,
+                        blur_vertical,
+                        combine,
+                    } = bloom_passes.as_ref();
+                    let bloom = targets
+                        .bloom
+                        .as_ref()
+                        .expect("Bloom targets were uninitialized when applying bloom");
+                    let (first, second) = (bloom[0].texture(), bloom[1].texture());
+
+                    // Extracted into the first target, blurred across into the second,
+                    // then down back into the first.
+                    offscreen.render(&bloom[0], None, |draw| {
+                        extract.draw(
+                            draw,
+                            &current,
+                            &current,
+                            &constants(&current, first_params([*threshold, 0.0, 0.0, 0.0])),
+                        );
+                    });
+                    offscreen.render(&bloom[1], None, |draw| {
+                        blur_horizontal.draw(
+                            draw,
+                            &first,
+                            &first,
+                            &constants(&first, first_params([*radius, 0.0, 0.0, 0.0])),
+                        );
+                    });
+                    offscreen.render(&bloom[0], None, |draw| {
+                        blur_vertical.draw(
+                            draw,
+                            &second,
+                            &second,
+                            &constants(&second, first_params([0.0, *radius, 0.0, 0.0])),
+                        );
+                    });
+                    offscreen.render(target, None, |draw| {
+                        combine.draw(
+                            draw,
+                            &first,
+                            &current,
+                            &constants(&first, first_params([*intensity, 0.0, 0.0, 0.0])),
+                        );
+                    });
+                }
+                (effect, EffectPasses::Single(pass)) => {
+                    let (extra, params) = match effect {
+                        Effect::Vignette {
+                            intensity,
+                            radius,
+                            softness,
+                        } => (
+                            current.clone(),
+                            first_params([*intensity, *radius, *softness, 0.0]),
+                        ),
+                        Effect::ColorGrading { lut, intensity } => (
+                            lut.clone(),
+                            first_params([lut.height() as f32, *intensity, 0.0, 0.0]),
+                        ),
+                        Effect::Crt {
+                            curvature,
+                            scanline_intensity,
+                        } => (
+                            current.clone(),
+                            first_params([*curvature, *scanline_intensity, 0.0, 0.0]),
+                        ),
+                        Effect::Custom(custom) => (
+                            custom.texture.clone().unwrap_or_else(|| current.clone()),
+                            custom.params,
+                        ),
+                        _ => (current.clone(), [[0.0; 4]; 4]),
+                    };
+
+                    offscreen.render(target, None, |draw| {
+                        pass.draw(draw, &current, &extra, &constants(&current, params));
+                    });
+                }
+                _ => unreachable!("Post process effects are rebuilt when their kind changes"),
+            }
+
+            current = target.texture();
+            next = 1 - next;
+        }
+
+        current
+    }
+}
@@ -0,0 +1,143 @@
+use std::{mem, rc};
+
+use ash::vk;
+
+use crate::graphics::{texture, vk_base::*, vk_resources::*, Draw, Resources};
+
+// Every pass's fragment shader gets these as push constants.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PassConstants {
+    // The size of one of the source's texels in uvs, for sampling its neighbours.
+    pub texel_size: [f32; 2],
+    // Seconds since the effects were set.
+    pub time: f32,
+    pub padding: f32,
+    pub params: [[f32; 4]; 4],
+}
+
+// A fragment shader drawn over the whole target, sampling a source texture and an extra
+// one, eg: a LUT. The pass samples them with its own filter rather than theirs.
+pub struct Pass {
+    device_data: rc::Rc<device_data::DeviceData>,
+    pipeline: pipeline::Pipeline,
+    sampler: vk::Sampler,
+}
+
+impl Pass {
+    pub fn new(resources: &Resources, fragment_spv: &[u8], filter: texture::Filter) -> Self {
+        let descriptor_layout_bindings = [
+            vk::DescriptorSetLayoutBinding {
+                descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::FRAGMENT,
+                ..Default::default()
+            },
+            vk::DescriptorSetLayoutBinding {
+                binding: 1,
+                descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::FRAGMENT,
+                ..Default::default()
+            },
+        ];
+
+        unsafe {
+            // The vertex shader makes the triangle from the vertex index alone.
+            let pipeline = pipeline::Pipeline::new(
+                resources,
+                pipeline::PipelineInfo {
+                    vertex_spv: include_bytes!("../../../shader/fullscreen.vert.spv"),
+                    fragment_spv,
+                    descriptor_layout_bindings: &descriptor_layout_bindings,
+                    vertex_input_binding_descriptions: &[],
+                    vertex_input_attribute_descriptions: &[],
+                    push_constant_ranges: &[vk::PushConstantRange {
+                        stage_flags: vk::ShaderStageFlags::FRAGMENT,
+                        offset: 0,
+                        size: mem::size_of::<PassConstants>() as u32,
+                    }],
+                    blend: pipeline::Blend::Opaque,
+                    depth_write: false,
+                },
+            );
+
+            let sampler = texture::Texture::new_sampler(
+                resources,
+                filter,
+                vk::SamplerAddressMode::CLAMP_TO_EDGE,
+            );
+
+            Self {
+                device_data: resources.base.device_data.clone(),
+                pipeline,
+                sampler,
+            }
+        }
+    }
+
+    pub fn draw(
+        &self,
+        draw: &Draw,
+        source: &texture::Texture,
+        extra: &texture::Texture,
+        constants: &PassConstants,
+    ) {
+        let source_descriptor = vk::DescriptorImageInfo {
+            sampler: self.sampler,
+            ..source.descriptor
+        };
+        let extra_descriptor = vk::DescriptorImageInfo {
+            sampler: self.sampler,
+            ..extra.descriptor
+        };
+
+        unsafe {
+            let descriptor_set = self.pipeline.descriptor_set(draw);
+            let write_descriptor_sets = [
+                vk::WriteDescriptorSet {
+                    dst_set: descriptor_set,
+                    dst_binding: 0,
+                    descriptor_count: 1,
+                    descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                    p_image_info: &source_descriptor,
+                    ..Default::default()
+                },
+                vk::WriteDescriptorSet {
+                    dst_set: descriptor_set,
+                    dst_binding: 1,
+                    descriptor_count: 1,
+                    descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                    p_image_info: &extra_descriptor,
+                    ..Default::default()
+                },
+            ];
+
+            draw.device
+                .update_descriptor_sets(&write_descriptor_sets, &[]);
+
+            self.pipeline.bind(draw, descriptor_set);
+
+            draw.device.cmd_push_constants(
+                draw.command_buffer,
+                self.pipeline.layout(),
+                vk::ShaderStageFlags::FRAGMENT,
+                0,
+                std::slice::from_raw_parts(
+                    constants as *const PassConstants as *const u8,
+                    mem::size_of::<PassConstants>(),
+                ),
+            );
+            draw.device.cmd_draw(draw.command_buffer, 3, 1, 0, 0);
+        }
+    }
+}
+
+impl Drop for Pass {
+    fn drop(&mut self) {
+        unsafe {
+            self.device_data.device.device_wait_idle().unwrap();
+            self.device_data.device.destroy_sampler(self.sampler, None);
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use std::{mem, rc};
+
+use ash::vk;
+use vk_mem::Alloc;
+
+use super::{color::Color, mat4, texture, vk_base::*, vk_resources::*, Resources};
+
+// An offscreen image that renderers draw into during App::draw_offscreen, and that's
+// then usable as a texture, eg: for minimaps, portals, or caching UI that rarely changes.
+// It keeps what was drawn into it until it's cleared. A batch can be drawn into targets
+// and to the screen in the same frame, each draw getting its own descriptors.
+pub struct RenderTarget {
+    width: u32,
+    height: u32,
+
+    device_data: rc::Rc<device_data::DeviceData>,
+
+    texture: rc::Rc<texture::Texture>,
+    render_pass: render_pass::RenderPass,
+
+    // Maintain the projection buffer until the target is dropped.
+    #[allow(dead_code)]
+    projection_matrix_buffer: buffer::Buffer,
+    projection_matrix_buffer_descriptor: vk::DescriptorBufferInfo,
+
+    depth: bool,
+    depth_image: vk::Image,
+    depth_image_view: vk::ImageView,
+    depth_allocation: mem::ManuallyDrop<vk_mem::Allocation>,
+}
+
+impl RenderTarget {
+    // Without depth, sprites are still depth tested against each other within a pass, but the
+    // depth image is transient so tile based GPUs may not need memory for it.
+    pub fn new(
+        resources: &Resources,
+        width: u32,
+        height: u32,
+        filter: texture::Filter,
+        depth: bool,
+    ) -> Self {
+        let texture = rc::Rc::new(texture::Texture::color_attachment(
+            resources, width, height, filter,
+        ));
+        let extent = vk::Extent2D { width, height };
+
+        unsafe {
+            let depth_usage = if depth {
+                vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT
+            } else {
+                vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT
+                    | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT
+            };
+            let depth_image_info = vk::ImageCreateInfo {
+                image_type: vk::ImageType::TYPE_2D,
+                format: vk::Format::D16_UNORM,
+                extent: extent.into(),
+                mip_levels: 1,
+                array_layers: 1,
+                samples: vk::SampleCountFlags::TYPE_1,
+                tiling: vk::ImageTiling::OPTIMAL,
+                usage: depth_usage,
+                sharing_mode: vk::SharingMode::EXCLUSIVE,
+                ..Default::default()
+            };
+
+            let allocation_info = vk_mem::AllocationCreateInfo {
+                usage: vk_mem::MemoryUsage::Auto,
+                ..Default::default()
+            };
+
+            let (depth_image, depth_allocation) = resources
+                .base
+                .device_data
+                .allocator
+                .create_image(&depth_image_info, &allocation_info)
+                .unwrap();
+
+            let depth_image_view_info = vk::ImageViewCreateInfo {
+                view_type: vk::ImageViewType::TYPE_2D,
+                format: depth_image_info.format,
+                subresource_range: vk::ImageSubresourceRange {
+                    aspect_mask: vk::ImageAspectFlags::DEPTH,
+                    level_count: 1,
+                    layer_count: 1,
+                    ..Default::default()
+                },
+                image: depth_image,
+                ..Default::default()
+            };
+
+            let depth_image_view = resources
+                .base
+                .device_data
+                .device
+                .create_image_view(&depth_image_view_info, None)
+                .unwrap();
+
+            let render_pass = render_pass::RenderPass::new_offscreen(
+                resources.base.device_data.clone(),
+                &resources.base.surface_data,
+                texture.descriptor.image_view,
+                depth_image_view,
+                extent,
+            );
+
+            // Sprites are measured in the target's pixels while drawing into it.
+            let mut projection_matrix = [0.0; 16];
+            mat4::orthographic_projection(
+                &mut projection_matrix,
+                mat4::OrthographicProjectionInfo {
+                    left: 0.0,
+                    right: width as f32,
+                    bottom: 0.0,
+                    top: height as f32,
+                    z_near: -mat4::Z_VIEW_DISTANCE,
+                    z_far: mat4::Z_VIEW_DISTANCE,
+                },
+            );
+            let projection_matrix_buffer = buffer::Buffer::new(
+                &projection_matrix,
+                resources.base.device_data.clone(),
+                vk::BufferUsageFlags::UNIFORM_BUFFER,
+            );
+            let projection_matrix_buffer_descriptor = vk::DescriptorBufferInfo {
+                buffer: projection_matrix_buffer.vk_buffer(),
+                offset: 0,
+                range: mem::size_of_val(&projection_matrix) as u64,
+            };
+
+            Self {
+                width,
+                height,
+
+                device_data: resources.base.device_data.clone(),
+
+                texture,
+                render_pass,
+
+                projection_matrix_buffer,
+                projection_matrix_buffer_descriptor,
+
+                depth,
+                depth_image,
+                depth_image_view,
+                depth_allocation: mem::ManuallyDrop::new(depth_allocation),
+            }
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn has_depth(&self) -> bool {
+        self.depth
+    }
+
+    // What was drawn into the target, eg: for a SpriteBatch.
+    pub fn texture(&self) -> rc::Rc<texture::Texture> {
+        self.texture.clone()
+    }
+
+    pub(super) fn projection_matrix_buffer_descriptor(&self) -> vk::DescriptorBufferInfo {
+        self.projection_matrix_buffer_descriptor
+    }
+
+    pub(super) fn extent(&self) -> vk::Extent2D {
+        vk::Extent2D {
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    pub(super) fn vk_viewport(&self) -> vk::Viewport {
+        vk::Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: self.width as f32,
+            height: self.height as f32,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        }
+    }
+
+    pub(super) unsafe fn begin(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        clear_color: Option<Color>,
+    ) {
+        let clear_values = [
+            vk::ClearValue::default(),
+            vk::ClearValue {
+                depth_stencil: vk::ClearDepthStencilValue {
+                    depth: 1.0,
+                    stencil: 0,
+                },
+            },
+        ];
+
+        self.render_pass
+            .begin(device, command_buffer, 0, self.extent(), &clear_values);
+
+        // The color image is loaded, so it's cleared here only when asked to.
+        if let Some(clear_color) = clear_color {
+            device.cmd_clear_attachments(
+                command_buffer,
+                &[vk::ClearAttachment {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    color_attachment: 0,
+                    clear_value: vk::ClearValue {
+                        color: vk::ClearColorValue {
+                            float32: clear_color.to_array(),
+                        },
+                    },
+                }],
+                &[vk::ClearRect {
+                    rect: self.extent().into(),
+                    base_array_layer: 0,
+                    layer_count: 1,
+                }],
+            );
+        }
+    }
+
+    pub(super) unsafe fn end(&self, device: &ash::Device, command_buffer: vk::CommandBuffer) {
+        self.render_pass.end(device, command_buffer);
+    }
+}
+
+impl Drop for RenderTarget {
+    fn drop(&mut self) {
+        unsafe {
+            self.device_data.device.device_wait_idle().unwrap();
+
+            self.device_data
+                .device
+                .destroy_image_view(self.depth_image_view, None);
+            self.device_data
+                .allocator
+                .free_memory(mem::ManuallyDrop::take(&mut self.depth_allocation));
+            self.device_data
+                .device
+                .destroy_image(self.depth_image, None);
+        }
+    }
+}
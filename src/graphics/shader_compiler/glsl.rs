@@ -0,0 +1,89 @@
+// Samplers get bindings from here on, they're dropped again once the SPIR-V is combined.
+const SAMPLER_BINDING_START: u32 = 1000;
+
+// Naga's GLSL frontend doesn't take combined sampler2Ds, so each one is split into a texture2D
+// and a sampler that are combined again wherever the texture's used. Declarations are
+// rewritten in place so errors still point at the right line.
+pub fn split_samplers(source: &str) -> String {
+    let mut names = Vec::new();
+    let mut lines = Vec::new();
+    for line in source.lines() {
+        match sampler_declaration(line) {
+            Some((layout, name)) => {
+                let binding = SAMPLER_BINDING_START + names.len() as u32;
+                lines.push(format!(
+                    "{} uniform texture2D {}; layout (set = 0, binding = {}) uniform sampler {}_sampler_;",
+                    layout, name, binding, name
+                ));
+                names.push(name);
+            }
+            None => lines.push(line.to_string()),
+        }
+    }
+
+    let mut source = lines.join("\n");
+    for name in &names {
+        source = replace_uses(&source, name);
+    }
+
+    source
+}
+
+// eg: "layout (binding = 1) uniform sampler2D sampler_color;" is ("layout (binding = 1)",
+// "sampler_color").
+fn sampler_declaration(line: &str) -> Option<(String, String)> {
+    let line = line.trim();
+    let layout_end = line.find(')')? + 1;
+    let (layout, declaration) = line.split_at(layout_end);
+    if !layout.starts_with("layout") {
+        return None;
+    }
+
+    let mut words = declaration.split_whitespace();
+    if words.next() != Some("uniform") || words.next() != Some("sampler2D") {
+        return None;
+    }
+    let name = words.next()?.strip_suffix(';')?;
+    if words.next().is_some() || !name.chars().all(is_identifier_char) {
+        return None;
+    }
+
+    Some((layout.to_string(), name.to_string()))
+}
+
+// Every use of the texture outside of its declaration samples it with its sampler.
+fn replace_uses(source: &str, name: &str) -> String {
+    let declaration = format!("uniform texture2D {};", name);
+    let replacement = format!("sampler2D({}, {}_sampler_)", name, name);
+
+    source
+        .lines()
+        .map(|line| {
+            if line.contains(&declaration) {
+                return line.to_string();
+            }
+
+            let mut result = String::new();
+            let mut rest = line;
+            while let Some(index) = rest.find(name) {
+                let before = rest[..index].chars().next_back();
+                let after = rest[index + name.len()..].chars().next();
+                result.push_str(&rest[..index]);
+                if before.is_some_and(is_identifier_char) || after.is_some_and(is_identifier_char) {
+                    result.push_str(name);
+                } else {
+                    result.push_str(&replacement);
+                }
+                rest = &rest[index + name.len()..];
+            }
+            result.push_str(rest);
+
+            result
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
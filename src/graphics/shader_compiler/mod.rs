@@ -0,0 +1,172 @@
+mod glsl;
+mod spirv;
+mod watcher;
+
+pub use watcher::ShaderWatcher;
+
+use std::{fmt, fs, path};
+
+// Compiles shaders to SPIR-V while the game runs, so they can be edited without rebuilding
+// the prebuilt .spv files by hand. GLSL is written the same way as the shaders in shader/,
+// while WGSL declares a texture and a sampler that are combined, and its entry point has
+// to be called main, eg:
+//
+//     @group(0) @binding(1) var sampler_color: texture_2d<f32>;
+//     @group(0) @binding(1001) var sampler_color_sampler: sampler;
+//
+//     @fragment
+//     fn main(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
+//         return textureSample(sampler_color, sampler_color_sampler, uv);
+//     }
+//
+// Textures that are sampled can't also be queried or fetched from, eg: with textureSize.
+// Naga's GLSL frontend has no atomics either, so compute shaders using them, like
+// shader/gpu_particle.comp, fail with "Unknown function 'atomicAdd'". They can be written
+// in WGSL, which has them, or stay prebuilt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    Vertex,
+    Fragment,
+    Compute,
+}
+
+impl Stage {
+    fn naga_stage(self) -> naga::ShaderStage {
+        match self {
+            Stage::Vertex => naga::ShaderStage::Vertex,
+            Stage::Fragment => naga::ShaderStage::Fragment,
+            Stage::Compute => naga::ShaderStage::Compute,
+        }
+    }
+}
+
+// Every error in a shader with where it is, eg: "shader/dissolve.frag:12: Unknown variable"
+// followed by the line.
+#[derive(Clone, Debug)]
+pub struct CompileError {
+    pub message: String,
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+// The language comes from the extension: .vert, .frag and .comp are GLSL, .wgsl is WGSL.
+// See Stage for what GLSL can't use.
+pub fn compile_file(path: &str) -> Result<Vec<u8>, CompileError> {
+    let source = fs::read_to_string(path).map_err(|error| CompileError {
+        message: format!("Failed to load file {}: {}", path, error),
+    })?;
+
+    let extension = path::Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str());
+    match extension {
+        Some("vert") => compile(path, &source, Some(Stage::Vertex)),
+        Some("frag") => compile(path, &source, Some(Stage::Fragment)),
+        Some("comp") => compile(path, &source, Some(Stage::Compute)),
+        Some("wgsl") => compile(path, &source, None),
+        _ => Err(CompileError {
+            message: format!("Unknown shader extension {}", path),
+        }),
+    }
+}
+
+pub fn compile_glsl(source: &str, stage: Stage) -> Result<Vec<u8>, CompileError> {
+    compile("glsl", source, Some(stage))
+}
+
+pub fn compile_wgsl(source: &str) -> Result<Vec<u8>, CompileError> {
+    compile("wgsl", source, None)
+}
+
+// GLSL is compiled for the given stage, while WGSL declares its own. GLSL's validation errors
+// show its split source, whose line numbers still match the file's.
+fn compile(name: &str, source: &str, stage: Option<Stage>) -> Result<Vec<u8>, CompileError> {
+    match stage {
+        Some(stage) => {
+            let split_source = glsl::split_samplers(source);
+            let module = parse_glsl(name, source, &split_source, stage)?;
+            write_spv(name, &split_source, &module)
+        }
+        None => write_spv(name, source, &parse_wgsl(name, source)?),
+    }
+}
+
+// Naga parses the split source, which only differs from the original within lines, so errors
+// are shown with the original line rather than a column.
+fn parse_glsl(
+    name: &str,
+    source: &str,
+    split_source: &str,
+    stage: Stage,
+) -> Result<naga::Module, CompileError> {
+    naga::front::glsl::Frontend::default()
+        .parse(
+            &naga::front::glsl::Options::from(stage.naga_stage()),
+            split_source,
+        )
+        .map_err(|errors| {
+            let messages: Vec<String> = errors
+                .iter()
+                .map(|error| {
+                    let line_number = error.meta.location(split_source).line_number;
+                    let line = source.lines().nth(line_number as usize - 1).unwrap_or("");
+                    format!(
+                        "{}:{}: {}\n{:>5} | {}",
+                        name,
+                        line_number,
+                        error.kind,
+                        line_number,
+                        line.trim_end()
+                    )
+                })
+                .collect();
+
+            CompileError {
+                message: messages.join("\n"),
+            }
+        })
+}
+
+fn parse_wgsl(name: &str, source: &str) -> Result<naga::Module, CompileError> {
+    naga::front::wgsl::parse_str(source).map_err(|error| CompileError {
+        message: error.emit_to_string_with_path(source, name),
+    })
+}
+
+fn write_spv(name: &str, source: &str, module: &naga::Module) -> Result<Vec<u8>, CompileError> {
+    let info = naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::all(),
+    )
+    .validate(module)
+    .map_err(|error| CompileError {
+        message: error.emit_to_string_with_path(source, name),
+    })?;
+
+    // Positions are already in Vulkan's coordinates, like with the prebuilt shaders, so naga
+    // shouldn't flip y.
+    let mut options = naga::back::spv::Options {
+        lang_version: (1, 0),
+        ..Default::default()
+    };
+    options
+        .flags
+        .remove(naga::back::spv::WriterFlags::ADJUST_COORDINATE_SPACE);
+
+    let words = naga::back::spv::write_vec(module, &info, &options, None).map_err(|error| {
+        CompileError {
+            message: format!("{}: {}", name, error),
+        }
+    })?;
+    let words = spirv::combine_image_samplers(&words).map_err(|message| CompileError {
+        message: format!("{}: {}", name, message),
+    })?;
+
+    Ok(words.iter().flat_map(|word| word.to_le_bytes()).collect())
+}
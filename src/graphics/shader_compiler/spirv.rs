@@ -0,0 +1,170 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+const OP_NAME: u32 = 5;
+const OP_DECORATE: u32 = 71;
+const OP_TYPE_IMAGE: u32 = 25;
+const OP_TYPE_SAMPLER: u32 = 26;
+const OP_TYPE_SAMPLED_IMAGE: u32 = 27;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_VARIABLE: u32 = 59;
+const OP_LOAD: u32 = 61;
+const OP_SAMPLED_IMAGE: u32 = 86;
+
+// Instructions that take an image rather than a sampled image as their third operand.
+const IMAGE_OPS: [u32; 9] = [95, 96, 97, 98, 100, 103, 104, 106, 107];
+
+const STORAGE_UNIFORM_CONSTANT: u32 = 0;
+const HEADER_LEN: usize = 5;
+const BOUND_INDEX: usize = 3;
+
+// Naga only knows separate textures and samplers, while pipelines here bind combined image
+// samplers. This turns each texture that's sampled into a combined image sampler with the
+// texture's binding, dropping the samplers, which are then left to the pipeline's descriptors.
+// Textures that are never used are dropped too, as they'd be declared as separate images.
+// Each instruction is kept as its opcode followed by its operands.
+pub fn combine_image_samplers(spv: &[u32]) -> Result<Vec<u32>, String> {
+    let mut header = spv[..HEADER_LEN].to_vec();
+    let mut instructions = Vec::new();
+    let mut i = HEADER_LEN;
+    while i < spv.len() {
+        let word_count = (spv[i] >> 16) as usize;
+        let mut instruction = vec![spv[i] & 0xffff];
+        instruction.extend_from_slice(&spv[i + 1..i + word_count]);
+        instructions.push(instruction);
+        i += word_count;
+    }
+    let mut bound = header[BOUND_INDEX];
+
+    let mut loads = HashMap::new();
+    let mut sampler_types = HashSet::new();
+    let mut image_types = HashSet::new();
+    let mut variable_types = HashMap::new();
+    let mut pointers = HashMap::new();
+    let mut pointees = HashMap::new();
+    let mut sampled_image_types = HashMap::new();
+    for instruction in &instructions {
+        match instruction[0] {
+            OP_LOAD => {
+                loads.insert(instruction[2], instruction[3]);
+            }
+            OP_TYPE_SAMPLER => {
+                sampler_types.insert(instruction[1]);
+            }
+            OP_TYPE_IMAGE => {
+                image_types.insert(instruction[1]);
+            }
+            OP_VARIABLE => {
+                variable_types.insert(instruction[2], instruction[1]);
+            }
+            OP_TYPE_POINTER => {
+                pointers.insert((instruction[2], instruction[3]), instruction[1]);
+                pointees.insert(instruction[1], instruction[3]);
+            }
+            OP_TYPE_SAMPLED_IMAGE => {
+                sampled_image_types.insert(instruction[1], instruction.clone());
+            }
+            _ => {}
+        }
+    }
+
+    // Which sampled image type each texture variable becomes.
+    let mut image_variables = BTreeMap::new();
+    let mut dropped_variables = HashSet::new();
+    for instruction in &instructions {
+        if instruction[0] == OP_SAMPLED_IMAGE {
+            let (image, sampler) = match (loads.get(&instruction[3]), loads.get(&instruction[4])) {
+                (Some(&image), Some(&sampler)) => (image, sampler),
+                _ => return Err("Textures and samplers can only be used directly".to_string()),
+            };
+            image_variables.insert(image, instruction[1]);
+            dropped_variables.insert(sampler);
+        }
+    }
+    let loaded: HashSet<u32> = loads.values().copied().collect();
+    for (&variable, pointer) in &variable_types {
+        let pointee = pointees.get(pointer);
+        let is_sampler = pointee.is_some_and(|pointee| sampler_types.contains(pointee));
+        let is_unused_image = pointee.is_some_and(|pointee| image_types.contains(pointee))
+            && !loaded.contains(&variable);
+        if is_sampler || is_unused_image {
+            dropped_variables.insert(variable);
+        }
+    }
+
+    let mut new_pointer_types = Vec::new();
+    let mut variable_pointers = HashMap::new();
+    for (&variable, &sampled_image_type) in &image_variables {
+        let pointer = *pointers
+            .entry((STORAGE_UNIFORM_CONSTANT, sampled_image_type))
+            .or_insert_with(|| {
+                new_pointer_types.push(vec![
+                    OP_TYPE_POINTER,
+                    bound,
+                    STORAGE_UNIFORM_CONSTANT,
+                    sampled_image_type,
+                ]);
+                bound += 1;
+                bound - 1
+            });
+        variable_pointers.insert(variable, pointer);
+    }
+
+    let dead_loads: HashSet<u32> = loads
+        .iter()
+        .filter(|(_, pointer)| {
+            image_variables.contains_key(pointer) || dropped_variables.contains(pointer)
+        })
+        .map(|(&id, _)| id)
+        .collect();
+
+    // Types have to be declared before the variables using them, so the sampled image types
+    // and their pointers are moved up to the first variable that needs them.
+    let mut emitted = HashSet::new();
+    let mut output = Vec::new();
+    for mut instruction in instructions {
+        match instruction[0] {
+            OP_NAME | OP_DECORATE if dropped_variables.contains(&instruction[1]) => continue,
+            OP_VARIABLE if dropped_variables.contains(&instruction[2]) => continue,
+            OP_LOAD if dead_loads.contains(&instruction[2]) => continue,
+            OP_TYPE_SAMPLED_IMAGE if !emitted.insert(instruction[1]) => continue,
+            OP_VARIABLE if variable_pointers.contains_key(&instruction[2]) => {
+                let pointer = variable_pointers[&instruction[2]];
+                let sampled_image_type = image_variables[&instruction[2]];
+                if emitted.insert(sampled_image_type) {
+                    output.push(sampled_image_types[&sampled_image_type].clone());
+                }
+                if let Some(pointer_type) = new_pointer_types.iter().find(|p| p[1] == pointer) {
+                    if emitted.insert(pointer) {
+                        output.push(pointer_type.clone());
+                    }
+                }
+                instruction[1] = pointer;
+            }
+            OP_SAMPLED_IMAGE => {
+                instruction = vec![
+                    OP_LOAD,
+                    instruction[1],
+                    instruction[2],
+                    loads[&instruction[3]],
+                ];
+            }
+            _ => {}
+        }
+        output.push(instruction);
+    }
+
+    if output.iter().any(|instruction| {
+        IMAGE_OPS.contains(&instruction[0]) && dead_loads.contains(&instruction[3])
+    }) {
+        return Err("Textures that are sampled can't also be queried or fetched from".to_string());
+    }
+
+    header[BOUND_INDEX] = bound;
+    let mut words = header;
+    for instruction in output {
+        words.push(((instruction.len() as u32) << 16) | instruction[0]);
+        words.extend_from_slice(&instruction[1..]);
+    }
+
+    Ok(words)
+}
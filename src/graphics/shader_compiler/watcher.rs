@@ -0,0 +1,120 @@
+use std::{collections::HashSet, path, rc, sync::mpsc};
+
+use notify::Watcher;
+
+struct WatchedShader {
+    path: path::PathBuf,
+    spv: rc::Rc<[u8]>,
+    // Earlier builds that might still be in use, to find the shader again from them.
+    previous: Vec<rc::Weak<[u8]>>,
+}
+
+// Recompiles shaders when their files change. Compiled shaders are shared as Rc<[u8]>, so
+// materials and custom effects can be looked up by them to be rebuilt with the latest build.
+pub struct ShaderWatcher {
+    watcher: notify::RecommendedWatcher,
+    events: mpsc::Receiver<notify::Result<notify::Event>>,
+    directories: HashSet<path::PathBuf>,
+    shaders: Vec<WatchedShader>,
+}
+
+impl ShaderWatcher {
+    pub fn new() -> Self {
+        let (sender, events) = mpsc::channel();
+        let watcher = notify::recommended_watcher(sender).expect("Failed to watch shader files");
+
+        Self {
+            watcher,
+            events,
+            directories: HashSet::new(),
+            shaders: Vec::new(),
+        }
+    }
+
+    // Panics if the shader doesn't compile the first time, as there's no build to fall back
+    // on, printing its errors.
+    pub fn load(&mut self, path: &str) -> rc::Rc<[u8]> {
+        let path = path::Path::new(path)
+            .canonicalize()
+            .unwrap_or_else(|_| panic!("Failed to load file {}", path));
+        if let Some(shader) = self.shaders.iter().find(|shader| shader.path == path) {
+            return shader.spv.clone();
+        }
+
+        let spv: rc::Rc<[u8]> = super::compile_file(&path.to_string_lossy())
+            .unwrap_or_else(|error| panic!("Failed to compile shader\n{}", error))
+            .into();
+
+        // Editors often save by replacing the file, so its directory is watched instead.
+        let directory = path.parent().unwrap().to_path_buf();
+        if self.directories.insert(directory.clone()) {
+            self.watcher
+                .watch(&directory, notify::RecursiveMode::NonRecursive)
+                .unwrap_or_else(|_| panic!("Failed to watch directory {}", directory.display()));
+        }
+
+        self.shaders.push(WatchedShader {
+            path,
+            spv: spv.clone(),
+            previous: Vec::new(),
+        });
+
+        spv
+    }
+
+    // The latest build of a watched shader, given an older one. None when it's already the
+    // latest, or isn't watched.
+    pub fn latest(&self, spv: &rc::Rc<[u8]>) -> Option<rc::Rc<[u8]>> {
+        self.shaders
+            .iter()
+            .find(|shader| {
+                shader
+                    .previous
+                    .iter()
+                    .any(|previous| previous.as_ptr() as *const u8 == spv.as_ptr())
+            })
+            .map(|shader| shader.spv.clone())
+    }
+
+    // Recompiles the shaders whose files changed since the last poll, returning whether any
+    // were rebuilt. A shader that fails to compile prints its errors and keeps its last build.
+    pub(in crate::graphics) fn poll(&mut self) -> bool {
+        let mut changed = HashSet::new();
+        for event in self.events.try_iter().flatten() {
+            if event.kind.is_modify() || event.kind.is_create() {
+                changed.extend(event.paths);
+            }
+        }
+
+        let mut rebuilt = false;
+        for shader in &mut self.shaders {
+            shader
+                .previous
+                .retain(|previous| previous.strong_count() > 0);
+            if !changed.contains(&shader.path) {
+                continue;
+            }
+
+            match super::compile_file(&shader.path.to_string_lossy()) {
+                Ok(spv) => {
+                    let spv: rc::Rc<[u8]> = spv.into();
+                    if *spv == *shader.spv {
+                        continue;
+                    }
+                    shader.previous.push(rc::Rc::downgrade(&shader.spv));
+                    shader.spv = spv;
+                    rebuilt = true;
+                }
+                Err(error) => eprintln!("Failed to compile shader\n{}", error),
+            }
+        }
+
+        rebuilt
+    }
+}
+
+impl Default for ShaderWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,570 @@
+use std::{
+    f32::consts::{PI, TAU},
+    mem, rc,
+};
+
+use ash::vk;
+
+use super::{color::Color, path, vec2::*, vk_base::*, vk_resources::*, *};
+
+// Shared with paths, so their joins match the other shapes'.
+pub(super) const MITER_LIMIT: f32 = 4.0;
+const ROUND_JOIN_STEP: f32 = PI / 8.0;
+const MIN_SEGMENTS: u32 = 3;
+
+#[derive(Clone, Debug, Copy)]
+pub struct Vertex {
+    pos: [f32; 3],
+    color: [f32; 4],
+}
+
+impl Vertex {
+    pub fn get_info() -> (
+        [vk::VertexInputBindingDescription; 1],
+        [vk::VertexInputAttributeDescription; 2],
+    ) {
+        let vertex_input_binding_descriptions = [vk::VertexInputBindingDescription {
+            binding: 0,
+            stride: mem::size_of::<Vertex>() as u32,
+            input_rate: vk::VertexInputRate::VERTEX,
+        }];
+        let vertex_input_attribute_descriptions = [
+            vk::VertexInputAttributeDescription {
+                location: 0,
+                binding: 0,
+                format: vk::Format::R32G32B32_SFLOAT,
+                offset: crate::offset_of!(Vertex, pos) as u32,
+            },
+            vk::VertexInputAttributeDescription {
+                location: 1,
+                binding: 0,
+                format: vk::Format::R32G32B32A32_SFLOAT,
+                offset: crate::offset_of!(Vertex, color) as u32,
+            },
+        ];
+
+        (
+            vertex_input_binding_descriptions,
+            vertex_input_attribute_descriptions,
+        )
+    }
+}
+
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+pub struct Ellipse {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub radius_x: f32,
+    pub radius_y: f32,
+    pub segments: u32,
+}
+
+impl Ellipse {
+    pub fn circle(x: f32, y: f32, z: f32, radius: f32, segments: u32) -> Self {
+        Self {
+            x,
+            y,
+            z,
+            radius_x: radius,
+            radius_y: radius,
+            segments,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineJoin {
+    Miter,
+    Round,
+    Bevel,
+}
+
+pub struct Stroke {
+    pub thickness: f32,
+    pub join: LineJoin,
+}
+
+pub struct ShapeBatch {
+    device_data: rc::Rc<device_data::DeviceData>,
+
+    index_buffer: Option<buffer::Buffer>,
+    vertex_buffer: Option<buffer::Buffer>,
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+
+    pipeline: pipeline::Pipeline,
+}
+
+impl ShapeBatch {
+    pub fn new(resources: &Resources) -> Self {
+        let descriptor_layout_bindings = [vk::DescriptorSetLayoutBinding {
+            descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+            descriptor_count: 1,
+            stage_flags: vk::ShaderStageFlags::VERTEX,
+            ..Default::default()
+        }];
+
+        let (vertex_input_binding_descriptions, vertex_input_attribute_descriptions) =
+            Vertex::get_info();
+
+        let pipeline = unsafe {
+            pipeline::Pipeline::new(
+                resources,
+                pipeline::PipelineInfo {
+                    vertex_spv: include_bytes!("../../shader/color.vert.spv"),
+                    fragment_spv: include_bytes!("../../shader/color.frag.spv"),
+                    descriptor_layout_bindings: &descriptor_layout_bindings,
+                    vertex_input_binding_descriptions: &vertex_input_binding_descriptions,
+                    vertex_input_attribute_descriptions: &vertex_input_attribute_descriptions,
+                    push_constant_ranges: &[],
+                    blend: pipeline::Blend::Alpha,
+                    depth_write: true,
+                },
+            )
+        };
+
+        Self {
+            device_data: resources.base.device_data.clone(),
+
+            index_buffer: None,
+            vertex_buffer: None,
+            vertices: Vec::new(),
+            indices: Vec::new(),
+
+            pipeline,
+        }
+    }
+
+    // Shapes are accumulated until the next call to upload, call clear to start a new set of shapes.
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+    }
+
+    pub fn fill_rect(&mut self, rect: &Rect, color: Color) {
+        self.push_quad(
+            [
+                [rect.x, rect.y],
+                [rect.x, rect.y + rect.height],
+                [rect.x + rect.width, rect.y + rect.height],
+                [rect.x + rect.width, rect.y],
+            ],
+            rect.z,
+            color,
+        );
+    }
+
+    // The outline is drawn inside of the rectangle's bounds.
+    pub fn stroke_rect(&mut self, rect: &Rect, thickness: f32, color: Color) {
+        let thickness = thickness.min(rect.width * 0.5).min(rect.height * 0.5);
+        let inner_height = rect.height - thickness * 2.0;
+
+        let sides = [
+            (rect.x, rect.y, rect.width, thickness),
+            (
+                rect.x,
+                rect.y + rect.height - thickness,
+                rect.width,
+                thickness,
+            ),
+            (rect.x, rect.y + thickness, thickness, inner_height),
+            (
+                rect.x + rect.width - thickness,
+                rect.y + thickness,
+                thickness,
+                inner_height,
+            ),
+        ];
+
+        for (x, y, width, height) in sides {
+            self.fill_rect(
+                &Rect {
+                    x,
+                    y,
+                    z: rect.z,
+                    width,
+                    height,
+                },
+                color,
+            );
+        }
+    }
+
+    pub fn fill_ellipse(&mut self, ellipse: &Ellipse, color: Color) {
+        let segments = ellipse.segments.max(MIN_SEGMENTS);
+        let center = self.push_vertex([ellipse.x, ellipse.y], ellipse.z, color);
+
+        for i in 0..segments {
+            let angle = i as f32 / segments as f32 * TAU;
+            self.push_vertex(
+                [
+                    ellipse.x + angle.cos() * ellipse.radius_x,
+                    ellipse.y + angle.sin() * ellipse.radius_y,
+                ],
+                ellipse.z,
+                color,
+            );
+        }
+
+        for i in 0..segments {
+            self.indices.push(center);
+            self.indices.push(center + 1 + i);
+            self.indices.push(center + 1 + (i + 1) % segments);
+        }
+    }
+
+    // The outline is centered on the ellipse's edge.
+    pub fn stroke_ellipse(&mut self, ellipse: &Ellipse, thickness: f32, color: Color) {
+        let segments = ellipse.segments.max(MIN_SEGMENTS);
+        let half_thickness = thickness * 0.5;
+        let first_vertex = self.vertices.len() as u32;
+
+        for i in 0..segments {
+            let angle = i as f32 / segments as f32 * TAU;
+            let (sin, cos) = angle.sin_cos();
+
+            self.push_vertex(
+                [
+                    ellipse.x + cos * (ellipse.radius_x + half_thickness),
+                    ellipse.y + sin * (ellipse.radius_y + half_thickness),
+                ],
+                ellipse.z,
+                color,
+            );
+            self.push_vertex(
+                [
+                    ellipse.x + cos * (ellipse.radius_x - half_thickness).max(0.0),
+                    ellipse.y + sin * (ellipse.radius_y - half_thickness).max(0.0),
+                ],
+                ellipse.z,
+                color,
+            );
+        }
+
+        for i in 0..segments {
+            let outer = first_vertex + i * 2;
+            let next_outer = first_vertex + (i + 1) % segments * 2;
+
+            self.indices.push(outer);
+            self.indices.push(outer + 1);
+            self.indices.push(next_outer + 1);
+            self.indices.push(next_outer + 1);
+            self.indices.push(next_outer);
+            self.indices.push(outer);
+        }
+    }
+
+    // Points must describe a convex polygon, concave polygons will be filled incorrectly.
+    pub fn fill_polygon(&mut self, points: &[Vec2], z: f32, color: Color) {
+        if points.len() < 3 {
+            return;
+        }
+
+        let first_vertex = self.vertices.len() as u32;
+
+        for point in points {
+            self.push_vertex(*point, z, color);
+        }
+
+        for i in 1..points.len() as u32 - 1 {
+            self.indices.push(first_vertex);
+            self.indices.push(first_vertex + i);
+            self.indices.push(first_vertex + i + 1);
+        }
+    }
+
+    pub fn stroke_polygon(&mut self, points: &[Vec2], z: f32, stroke: &Stroke, color: Color) {
+        self.push_stroke(points, true, z, stroke, color);
+    }
+
+    pub fn polyline(&mut self, points: &[Vec2], z: f32, stroke: &Stroke, color: Color) {
+        self.push_stroke(points, false, z, stroke, color);
+    }
+
+    // A path that can't be tessellated adds nothing, and returns why.
+    pub fn fill_path(
+        &mut self,
+        path: &path::Path,
+        z: f32,
+        fill_rule: path::FillRule,
+        color: Color,
+    ) -> Result<(), path::TessellationError> {
+        self.push_mesh(&path.tessellate_fill(fill_rule)?, z, color);
+        Ok(())
+    }
+
+    pub fn stroke_path(
+        &mut self,
+        path: &path::Path,
+        z: f32,
+        stroke: &path::PathStroke,
+        color: Color,
+    ) -> Result<(), path::TessellationError> {
+        self.push_mesh(&path.tessellate_stroke(stroke)?, z, color);
+        Ok(())
+    }
+
+    // Adds pre-tessellated triangles, useful for paths that are drawn often but rarely change.
+    pub fn push_mesh(&mut self, mesh: &path::Mesh, z: f32, color: Color) {
+        let first_vertex = self.vertices.len() as u32;
+
+        for point in &mesh.points {
+            self.push_vertex(*point, z, color);
+        }
+
+        self.indices
+            .extend(mesh.indices.iter().map(|index| first_vertex + index));
+    }
+
+    pub fn upload(&mut self) {
+        unsafe {
+            self.device_data.device.device_wait_idle().unwrap();
+
+            // Buffers can't be initialized with no data, so if either buffer
+            // has no data (ie: because no shapes were added) then return.
+            if self.indices.is_empty() || self.vertices.is_empty() {
+                self.index_buffer = None;
+                self.vertex_buffer = None;
+                return;
+            }
+
+            self.index_buffer = Some(buffer::Buffer::new(
+                &self.indices,
+                self.device_data.clone(),
+                vk::BufferUsageFlags::INDEX_BUFFER,
+            ));
+
+            self.vertex_buffer = Some(buffer::Buffer::new(
+                &self.vertices,
+                self.device_data.clone(),
+                vk::BufferUsageFlags::VERTEX_BUFFER,
+            ));
+        }
+    }
+
+    pub fn draw(&self, draw: &Draw) {
+        if self.vertex_buffer.is_none() || self.index_buffer.is_none() {
+            return;
+        }
+
+        let vertex_buffer = self.vertex_buffer.as_ref().unwrap();
+        let index_buffer = self.index_buffer.as_ref().unwrap();
+
+        unsafe {
+            let descriptor_set = self.pipeline.descriptor_set(draw);
+            let write_descriptor_sets = [vk::WriteDescriptorSet {
+                dst_set: descriptor_set,
+                dst_binding: 0,
+                descriptor_count: 1,
+                descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+                p_buffer_info: &draw.projection_matrix_buffer_descriptor,
+                ..Default::default()
+            }];
+
+            draw.device
+                .update_descriptor_sets(&write_descriptor_sets, &[]);
+
+            self.pipeline.bind(draw, descriptor_set);
+
+            draw.device.cmd_bind_vertex_buffers(
+                draw.command_buffer,
+                0,
+                &[vertex_buffer.vk_buffer()],
+                &[0],
+            );
+            draw.device.cmd_bind_index_buffer(
+                draw.command_buffer,
+                index_buffer.vk_buffer(),
+                0,
+                vk::IndexType::UINT32,
+            );
+            draw.device.cmd_draw_indexed(
+                draw.command_buffer,
+                index_buffer.len() as u32,
+                1,
+                0,
+                0,
+                1,
+            );
+        }
+    }
+
+    fn push_vertex(&mut self, point: Vec2, z: f32, color: Color) -> u32 {
+        let index = self.vertices.len() as u32;
+
+        self.vertices.push(Vertex {
+            pos: [point[0], point[1], z],
+            color: color.to_array(),
+        });
+
+        index
+    }
+
+    fn push_triangle(&mut self, points: [Vec2; 3], z: f32, color: Color) {
+        for point in points {
+            let index = self.push_vertex(point, z, color);
+            self.indices.push(index);
+        }
+    }
+
+    fn push_quad(&mut self, points: [Vec2; 4], z: f32, color: Color) {
+        let vertex_count = self.vertices.len() as u32;
+
+        for point in points {
+            self.push_vertex(point, z, color);
+        }
+
+        self.indices.push(vertex_count);
+        self.indices.push(vertex_count + 1);
+        self.indices.push(vertex_count + 2);
+        self.indices.push(vertex_count + 2);
+        self.indices.push(vertex_count + 3);
+        self.indices.push(vertex_count);
+    }
+
+    // Each segment is drawn as its own quad, then the gaps on the outside of each corner are filled in.
+    fn push_stroke(
+        &mut self,
+        points: &[Vec2],
+        is_closed: bool,
+        z: f32,
+        stroke: &Stroke,
+        color: Color,
+    ) {
+        if points.len() < 2 {
+            return;
+        }
+
+        let half_thickness = stroke.thickness * 0.5;
+        let segment_count = if is_closed {
+            points.len()
+        } else {
+            points.len() - 1
+        };
+
+        for i in 0..segment_count {
+            let start = points[i];
+            let end = points[(i + 1) % points.len()];
+
+            let Some(normal) = segment_normal(start, end) else {
+                continue;
+            };
+
+            let offset = scale(normal, half_thickness);
+
+            self.push_quad(
+                [
+                    add(start, offset),
+                    add(end, offset),
+                    sub(end, offset),
+                    sub(start, offset),
+                ],
+                z,
+                color,
+            );
+        }
+
+        let joins = if is_closed {
+            0..points.len()
+        } else {
+            1..points.len() - 1
+        };
+
+        for i in joins {
+            let previous = points[(i + points.len() - 1) % points.len()];
+            let next = points[(i + 1) % points.len()];
+
+            self.push_join(previous, points[i], next, z, stroke, color);
+        }
+    }
+
+    fn push_join(
+        &mut self,
+        previous: Vec2,
+        point: Vec2,
+        next: Vec2,
+        z: f32,
+        stroke: &Stroke,
+        color: Color,
+    ) {
+        let (Some(previous_normal), Some(next_normal)) =
+            (segment_normal(previous, point), segment_normal(point, next))
+        else {
+            return;
+        };
+
+        let turn = cross(sub(point, previous), sub(next, point));
+
+        if turn.abs() < f32::EPSILON {
+            return;
+        }
+
+        // The gap that needs to be filled is on the opposite side of the direction the line turns.
+        let outer_side = if turn > 0.0 { -1.0 } else { 1.0 };
+        let half_thickness = stroke.thickness * 0.5;
+        let previous_offset = scale(previous_normal, half_thickness * outer_side);
+        let next_offset = scale(next_normal, half_thickness * outer_side);
+        let previous_outer = add(point, previous_offset);
+        let next_outer = add(point, next_offset);
+
+        match stroke.join {
+            LineJoin::Miter => {
+                let miter = normalize(add(previous_normal, next_normal));
+                let miter_length = miter
+                    .map(|miter| half_thickness / dot(miter, next_normal))
+                    .filter(|miter_length| *miter_length <= half_thickness * MITER_LIMIT);
+
+                if let (Some(miter), Some(miter_length)) = (miter, miter_length) {
+                    let tip = add(point, scale(miter, miter_length * outer_side));
+                    self.push_quad([point, previous_outer, tip, next_outer], z, color);
+                } else {
+                    // Very sharp corners would produce extremely long miters, so bevel them instead.
+                    self.push_triangle([point, previous_outer, next_outer], z, color);
+                }
+            }
+            LineJoin::Round => {
+                let start_angle = previous_offset[1].atan2(previous_offset[0]);
+                let mut sweep = next_offset[1].atan2(next_offset[0]) - start_angle;
+
+                if sweep > PI {
+                    sweep -= TAU;
+                } else if sweep < -PI {
+                    sweep += TAU;
+                }
+
+                let steps = (sweep.abs() / ROUND_JOIN_STEP).ceil().max(1.0) as u32;
+                let center = self.push_vertex(point, z, color);
+                let arc_start = center + 1;
+
+                for step in 0..=steps {
+                    let angle = start_angle + sweep * step as f32 / steps as f32;
+                    self.push_vertex(
+                        [
+                            point[0] + angle.cos() * half_thickness,
+                            point[1] + angle.sin() * half_thickness,
+                        ],
+                        z,
+                        color,
+                    );
+                }
+
+                for step in 0..steps {
+                    self.indices.push(center);
+                    self.indices.push(arc_start + step);
+                    self.indices.push(arc_start + step + 1);
+                }
+            }
+            LineJoin::Bevel => {
+                self.push_triangle([point, previous_outer, next_outer], z, color);
+            }
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::{io, mem, rc};
+use std::{mem, rc};
 
 use ash::vk;
 
@@ -11,6 +11,10 @@ pub struct Vertex {
 }
 
 impl Vertex {
+    pub fn new(pos: [f32; 3], uv: [f32; 2]) -> Self {
+        Self { pos, uv }
+    }
+
     pub fn get_info() -> (
         [vk::VertexInputBindingDescription; 1],
         [vk::VertexInputAttributeDescription; 2],
@@ -42,12 +46,47 @@ impl Vertex {
     }
 }
 
+// A rectangle within a texture, in texture coordinates from 0 to 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Region {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Region {
+    pub const FULL: Self = Self {
+        x: 0.0,
+        y: 0.0,
+        width: 1.0,
+        height: 1.0,
+    };
+
+    pub fn from_pixels(
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        texture_width: f32,
+        texture_height: f32,
+    ) -> Self {
+        Self {
+            x: x / texture_width,
+            y: y / texture_height,
+            width: width / texture_width,
+            height: height / texture_height,
+        }
+    }
+}
+
 pub struct Sprite {
     pub x: f32,
     pub y: f32,
     pub z: f32,
     pub width: f32,
     pub height: f32,
+    pub region: Region,
 }
 
 pub struct SpriteBatch {
@@ -58,256 +97,147 @@ pub struct SpriteBatch {
     vertices: Vec<Vertex>,
     indices: Vec<u32>,
 
-    vertex_shader_module: vk::ShaderModule,
-    fragment_shader_module: vk::ShaderModule,
-
-    pipelines: Vec<vk::Pipeline>,
-    pipeline_layout: vk::PipelineLayout,
-
-    descriptor_sets: Vec<vk::DescriptorSet>,
-    descriptor_set_layouts: [vk::DescriptorSetLayout; 1],
-    descriptor_pool: vk::DescriptorPool,
+    pipeline: pipeline::Pipeline,
 
     texture: rc::Rc<texture::Texture>,
+
+    material: material::Material,
 }
 
 impl SpriteBatch {
     pub fn new(resources: &Resources, texture: rc::Rc<texture::Texture>) -> Self {
-        unsafe {
-            let descriptor_sizes = [
-                vk::DescriptorPoolSize {
-                    ty: vk::DescriptorType::UNIFORM_BUFFER,
-                    descriptor_count: 1,
-                },
-                vk::DescriptorPoolSize {
-                    ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
-                    descriptor_count: 1,
-                },
-            ];
-            let descriptor_pool_info = vk::DescriptorPoolCreateInfo::builder()
-                .pool_sizes(&descriptor_sizes)
-                .max_sets(1)
-                .build();
-            let descriptor_pool = resources
-                .base
-                .device_data
-                .device
-                .create_descriptor_pool(&descriptor_pool_info, None)
-                .unwrap();
-            let descriptor_layout_bindings = [
-                vk::DescriptorSetLayoutBinding {
-                    descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
-                    descriptor_count: 1,
-                    stage_flags: vk::ShaderStageFlags::FRAGMENT,
-                    ..Default::default()
-                },
-                vk::DescriptorSetLayoutBinding {
-                    binding: 1,
-                    descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
-                    descriptor_count: 1,
-                    stage_flags: vk::ShaderStageFlags::FRAGMENT,
-                    ..Default::default()
-                },
-                vk::DescriptorSetLayoutBinding {
-                    binding: 2,
-                    descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
-                    descriptor_count: 1,
-                    stage_flags: vk::ShaderStageFlags::VERTEX,
-                    ..Default::default()
-                },
-            ];
-            let descriptor_info = vk::DescriptorSetLayoutCreateInfo::builder()
-                .bindings(&descriptor_layout_bindings)
-                .build();
-
-            let descriptor_set_layouts = [resources
-                .base
-                .device_data
-                .device
-                .create_descriptor_set_layout(&descriptor_info, None)
-                .unwrap()];
-
-            let descriptor_alloc_info = vk::DescriptorSetAllocateInfo::builder()
-                .descriptor_pool(descriptor_pool)
-                .set_layouts(&descriptor_set_layouts)
-                .build();
-            let descriptor_sets = resources
-                .base
-                .device_data
-                .device
-                .allocate_descriptor_sets(&descriptor_alloc_info)
-                .unwrap();
-
-            // TODO: Make shader a seperate resoure that gets passed in here?
-            let mut vertex_spv_file =
-                io::Cursor::new(&include_bytes!("../../shader/texture.vert.spv")[..]);
-            let mut frag_spv_file =
-                io::Cursor::new(&include_bytes!("../../shader/texture.frag.spv")[..]);
-
-            let vertex_code =
-                read_spv(&mut vertex_spv_file).expect("Failed to read vertex shader spv file");
-            let vertex_shader_info = vk::ShaderModuleCreateInfo::builder()
-                .code(&vertex_code)
-                .build();
-
-            let frag_code =
-                read_spv(&mut frag_spv_file).expect("Failed to read fragment shader spv file");
-            let frag_shader_info = vk::ShaderModuleCreateInfo::builder()
-                .code(&frag_code)
-                .build();
-
-            let vertex_shader_module = resources
-                .base
-                .device_data
-                .device
-                .create_shader_module(&vertex_shader_info, None)
-                .expect("Failed to create vertex shader module");
-
-            let fragment_shader_module = resources
-                .base
-                .device_data
-                .device
-                .create_shader_module(&frag_shader_info, None)
-                .expect("Failed to create fragment shader module");
-
-            let layout_create_info = vk::PipelineLayoutCreateInfo::builder()
-                .set_layouts(&descriptor_set_layouts)
-                .build();
-
-            let pipeline_layout = resources
-                .base
-                .device_data
-                .device
-                .create_pipeline_layout(&layout_create_info, None)
-                .unwrap();
-
-            let shader_entry_name = CStr::from_bytes_with_nul_unchecked(b"main\0");
-            let shader_stage_create_infos = [
-                vk::PipelineShaderStageCreateInfo {
-                    module: vertex_shader_module,
-                    p_name: shader_entry_name.as_ptr(),
-                    stage: vk::ShaderStageFlags::VERTEX,
-                    ..Default::default()
-                },
-                vk::PipelineShaderStageCreateInfo {
-                    module: fragment_shader_module,
-                    p_name: shader_entry_name.as_ptr(),
-                    stage: vk::ShaderStageFlags::FRAGMENT,
-                    ..Default::default()
-                },
-            ];
+        Self::with_material(resources, texture, material::Material::sprite())
+    }
 
-            let viewport_state_info = vk::PipelineViewportStateCreateInfo::builder()
-                .scissors(&resources.scissors)
-                .viewports(&resources.viewports)
-                .build();
+    pub fn with_material(
+        resources: &Resources,
+        texture: rc::Rc<texture::Texture>,
+        material: material::Material,
+    ) -> Self {
+        let pipeline = Self::new_pipeline(resources, &material);
 
-            let rasterization_info = vk::PipelineRasterizationStateCreateInfo {
-                front_face: vk::FrontFace::COUNTER_CLOCKWISE,
-                line_width: 1.0,
-                polygon_mode: vk::PolygonMode::FILL,
-                ..Default::default()
-            };
+        Self {
+            device_data: resources.base.device_data.clone(),
 
-            let multisample_state_info = vk::PipelineMultisampleStateCreateInfo::builder()
-                .rasterization_samples(vk::SampleCountFlags::TYPE_1)
-                .build();
+            index_buffer: None,
+            vertex_buffer: None,
+            vertices: Vec::new(),
+            indices: Vec::new(),
+
+            pipeline,
+
+            texture,
+
+            material,
+        }
+    }
 
-            let noop_stencil_state = vk::StencilOpState {
-                fail_op: vk::StencilOp::KEEP,
-                pass_op: vk::StencilOp::KEEP,
-                depth_fail_op: vk::StencilOp::KEEP,
-                compare_op: vk::CompareOp::ALWAYS,
+    fn new_pipeline(resources: &Resources, material: &material::Material) -> pipeline::Pipeline {
+        let mut descriptor_layout_bindings = vec![
+            vk::DescriptorSetLayoutBinding {
+                descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::FRAGMENT,
                 ..Default::default()
-            };
-            let depth_state_info = vk::PipelineDepthStencilStateCreateInfo {
-                depth_test_enable: 1,
-                depth_write_enable: 1,
-                depth_compare_op: vk::CompareOp::LESS_OR_EQUAL,
-                front: noop_stencil_state,
-                back: noop_stencil_state,
-                max_depth_bounds: 1.0,
+            },
+            vk::DescriptorSetLayoutBinding {
+                binding: 1,
+                descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::FRAGMENT,
                 ..Default::default()
-            };
-
-            let color_blend_attachment_states = [vk::PipelineColorBlendAttachmentState {
-                blend_enable: 0,
-                src_color_blend_factor: vk::BlendFactor::SRC_COLOR,
-                dst_color_blend_factor: vk::BlendFactor::ONE_MINUS_DST_COLOR,
-                color_blend_op: vk::BlendOp::ADD,
-                src_alpha_blend_factor: vk::BlendFactor::ZERO,
-                dst_alpha_blend_factor: vk::BlendFactor::ZERO,
-                alpha_blend_op: vk::BlendOp::ADD,
-                color_write_mask: vk::ColorComponentFlags::RGBA,
-            }];
-            let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
-                .logic_op(vk::LogicOp::CLEAR)
-                .attachments(&color_blend_attachment_states)
-                .build();
-
-            let dynamic_state = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
-            let dynamic_state_info = vk::PipelineDynamicStateCreateInfo::builder()
-                .dynamic_states(&dynamic_state)
-                .build();
-
-            let (vertex_input_binding_descriptions, vertex_input_attribute_descriptions) =
-                Vertex::get_info();
-            let vertex_input_state_info = vk::PipelineVertexInputStateCreateInfo::builder()
-                .vertex_attribute_descriptions(&vertex_input_attribute_descriptions)
-                .vertex_binding_descriptions(&vertex_input_binding_descriptions)
-                .build();
-
-            let vertex_input_assembly_state_info = vk::PipelineInputAssemblyStateCreateInfo {
-                topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+            },
+            vk::DescriptorSetLayoutBinding {
+                binding: 2,
+                descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::VERTEX,
                 ..Default::default()
-            };
-
-            let graphic_pipeline_infos = vk::GraphicsPipelineCreateInfo::builder()
-                .stages(&shader_stage_create_infos)
-                .vertex_input_state(&vertex_input_state_info)
-                .input_assembly_state(&vertex_input_assembly_state_info)
-                .viewport_state(&viewport_state_info)
-                .rasterization_state(&rasterization_info)
-                .multisample_state(&multisample_state_info)
-                .depth_stencil_state(&depth_state_info)
-                .color_blend_state(&color_blend_state)
-                .dynamic_state(&dynamic_state_info)
-                .layout(pipeline_layout)
-                .render_pass(resources.render_pass.vk_render_pass)
-                .build();
-
-            let pipelines = resources
-                .base
-                .device_data
-                .device
-                .create_graphics_pipelines(
-                    vk::PipelineCache::null(),
-                    &[graphic_pipeline_infos],
-                    None,
-                )
-                .unwrap();
-
-            Self {
-                device_data: resources.base.device_data.clone(),
-
-                index_buffer: None,
-                vertex_buffer: None,
-                vertices: Vec::new(),
-                indices: Vec::new(),
-
-                vertex_shader_module,
-                fragment_shader_module,
-
-                pipelines,
-                pipeline_layout,
-
-                descriptor_sets,
-                descriptor_set_layouts,
-                descriptor_pool,
-
-                texture,
-            }
+            },
+        ];
+        for i in 0..material.textures.len() {
+            descriptor_layout_bindings.push(vk::DescriptorSetLayoutBinding {
+                binding: 3 + i as u32,
+                descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::FRAGMENT,
+                ..Default::default()
+            });
+        }
+
+        let (vertex_input_binding_descriptions, vertex_input_attribute_descriptions) =
+            Vertex::get_info();
+
+        // The params are push constants, so they can change every frame without waiting on
+        // the GPU to finish with a buffer.
+        let push_constant_ranges = [vk::PushConstantRange {
+            stage_flags: vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+            offset: 0,
+            size: mem::size_of_val(&material.params) as u32,
+        }];
+
+        unsafe {
+            pipeline::Pipeline::new(
+                resources,
+                pipeline::PipelineInfo {
+                    vertex_spv: &material.shader.vertex_spv,
+                    fragment_spv: &material.shader.fragment_spv,
+                    descriptor_layout_bindings: &descriptor_layout_bindings,
+                    vertex_input_binding_descriptions: &vertex_input_binding_descriptions,
+                    vertex_input_attribute_descriptions: &vertex_input_attribute_descriptions,
+                    push_constant_ranges: &push_constant_ranges,
+                    blend: match material.blend {
+                        material::Blend::Opaque => pipeline::Blend::Opaque,
+                        material::Blend::Alpha => pipeline::Blend::Alpha,
+                        material::Blend::Additive => pipeline::Blend::Additive,
+                    },
+                    depth_write: material.blend == material::Blend::Opaque,
+                },
+            )
+        }
+    }
+
+    pub fn material(&self) -> &material::Material {
+        &self.material
+    }
+
+    // The pipeline is only rebuilt when the shader, blend or number of textures changes, which
+    // waits for the GPU to finish with the old one, so swapping textures or params between
+    // compatible materials is cheap.
+    pub fn set_material(&mut self, resources: &Resources, material: material::Material) {
+        if !self.material.is_compatible(&material) {
+            self.pipeline = Self::new_pipeline(resources, &material);
+        }
+
+        self.material = material;
+    }
+
+    // Rebuilds the pipeline when the shader watcher recompiled the material's shaders. This
+    // isn't done on its own, so it has to be called for the batch to pick up edits, eg: once
+    // each update. It does nothing when the shaders haven't changed.
+    #[cfg(feature = "shader-compiler")]
+    pub fn reload_shaders(&mut self, resources: &Resources) {
+        let watcher = &resources.shader_watcher;
+        let vertex_spv = watcher.latest(&self.material.shader.vertex_spv);
+        let fragment_spv = watcher.latest(&self.material.shader.fragment_spv);
+        if vertex_spv.is_none() && fragment_spv.is_none() {
+            return;
         }
+
+        let shader = material::Shader {
+            vertex_spv: vertex_spv.unwrap_or_else(|| self.material.shader.vertex_spv.clone()),
+            fragment_spv: fragment_spv.unwrap_or_else(|| self.material.shader.fragment_spv.clone()),
+        };
+        let material = material::Material {
+            shader: rc::Rc::new(shader),
+            ..self.material.clone()
+        };
+        self.set_material(resources, material);
+    }
+
+    // eg: to animate a dissolve's threshold each frame.
+    pub fn set_params(&mut self, params: [[f32; 4]; 4]) {
+        self.material.params = params;
     }
 
     pub fn batch(&mut self, sprites: &[Sprite]) {
@@ -316,22 +246,23 @@ impl SpriteBatch {
 
         for sprite in sprites {
             let vertex_count = self.vertices.len() as u32;
+            let region = sprite.region;
 
             self.vertices.push(Vertex {
                 pos: [sprite.x, sprite.y, sprite.z],
-                uv: [0.0, 0.0],
+                uv: [region.x, region.y],
             });
             self.vertices.push(Vertex {
                 pos: [sprite.x, sprite.y + sprite.height, sprite.z],
-                uv: [0.0, 1.0],
+                uv: [region.x, region.y + region.height],
             });
             self.vertices.push(Vertex {
                 pos: [sprite.x + sprite.width, sprite.y + sprite.height, sprite.z],
-                uv: [1.0, 1.0],
+                uv: [region.x + region.width, region.y + region.height],
             });
             self.vertices.push(Vertex {
                 pos: [sprite.x + sprite.width, sprite.y, sprite.z],
-                uv: [1.0, 0.0],
+                uv: [region.x + region.width, region.y],
             });
 
             self.indices.push(vertex_count);
@@ -376,9 +307,10 @@ impl SpriteBatch {
         let index_buffer = self.index_buffer.as_ref().unwrap();
 
         unsafe {
-            let write_descriptor_sets = [
+            let descriptor_set = self.pipeline.descriptor_set(draw);
+            let mut write_descriptor_sets = vec![
                 vk::WriteDescriptorSet {
-                    dst_set: self.descriptor_sets[0],
+                    dst_set: descriptor_set,
                     dst_binding: 1,
                     descriptor_count: 1,
                     descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
@@ -386,36 +318,40 @@ impl SpriteBatch {
                     ..Default::default()
                 },
                 vk::WriteDescriptorSet {
-                    dst_set: self.descriptor_sets[0],
+                    dst_set: descriptor_set,
                     dst_binding: 2,
                     descriptor_count: 1,
                     descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
-                    p_buffer_info: &draw.resources.projection_matrix_buffer_descriptor,
+                    p_buffer_info: &draw.projection_matrix_buffer_descriptor,
                     ..Default::default()
                 },
             ];
+            for (i, texture) in self.material.textures.iter().enumerate() {
+                write_descriptor_sets.push(vk::WriteDescriptorSet {
+                    dst_set: descriptor_set,
+                    dst_binding: 3 + i as u32,
+                    descriptor_count: 1,
+                    descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                    p_image_info: &texture.descriptor,
+                    ..Default::default()
+                });
+            }
 
             draw.device
                 .update_descriptor_sets(&write_descriptor_sets, &[]);
 
-            draw.device.cmd_bind_descriptor_sets(
+            self.pipeline.bind(draw, descriptor_set);
+
+            draw.device.cmd_push_constants(
                 draw.command_buffer,
-                vk::PipelineBindPoint::GRAPHICS,
-                self.pipeline_layout,
+                self.pipeline.layout(),
+                vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
                 0,
-                &self.descriptor_sets[..],
-                &[],
+                std::slice::from_raw_parts(
+                    self.material.params.as_ptr() as *const u8,
+                    mem::size_of_val(&self.material.params),
+                ),
             );
-            draw.device.cmd_bind_pipeline(
-                draw.command_buffer,
-                vk::PipelineBindPoint::GRAPHICS,
-                self.pipelines[0],
-            );
-            draw.device
-                .cmd_set_viewport(draw.command_buffer, 0, &draw.resources.viewports);
-            draw.device
-                .cmd_set_scissor(draw.command_buffer, 0, &draw.resources.scissors);
-
             draw.device.cmd_bind_vertex_buffers(
                 draw.command_buffer,
                 0,
@@ -439,32 +375,3 @@ impl SpriteBatch {
         }
     }
 }
-
-impl Drop for SpriteBatch {
-    fn drop(&mut self) {
-        unsafe {
-            self.device_data.device.device_wait_idle().unwrap();
-
-            for pipeline in &self.pipelines {
-                self.device_data.device.destroy_pipeline(*pipeline, None);
-            }
-            self.device_data
-                .device
-                .destroy_pipeline_layout(self.pipeline_layout, None);
-            self.device_data
-                .device
-                .destroy_shader_module(self.vertex_shader_module, None);
-            self.device_data
-                .device
-                .destroy_shader_module(self.fragment_shader_module, None);
-            for &descriptor_set_layout in self.descriptor_set_layouts.iter() {
-                self.device_data
-                    .device
-                    .destroy_descriptor_set_layout(descriptor_set_layout, None);
-            }
-            self.device_data
-                .device
-                .destroy_descriptor_pool(self.descriptor_pool, None);
-        }
-    }
-}
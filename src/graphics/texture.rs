@@ -2,6 +2,7 @@ use ash::vk;
 
 use std::{fs, io, mem, rc};
 
+use resvg::{tiny_skia, usvg};
 use vk_mem::Alloc;
 
 use crate::graphics::{vk_base::*, vk_resources::*, *};
@@ -14,11 +15,15 @@ pub enum Filter {
 pub struct Texture {
     pub descriptor: vk::DescriptorImageInfo,
 
+    width: u32,
+    height: u32,
+    storage: bool,
+
     device_data: rc::Rc<device_data::DeviceData>,
 
     // Maintain the texture's image buffer until the texture is dropped.
     #[allow(dead_code)]
-    image_buffer: buffer::Buffer,
+    image_buffer: Option<buffer::Buffer>,
 
     image_view: vk::ImageView,
     allocation: mem::ManuallyDrop<vk_mem::Allocation>,
@@ -34,12 +39,82 @@ impl Texture {
             .unwrap()
             .to_rgba8();
         let (width, height) = image.dimensions();
+
+        Self::from_rgba(resources, width, height, &image.into_raw(), filter)
+    }
+
+    // Rasterizes an SVG, stretching it to fill the requested size in pixels.
+    pub fn from_svg(
+        resources: &Resources,
+        path: &str,
+        width: u32,
+        height: u32,
+        filter: Filter,
+    ) -> Self {
+        Self::from_svg_tree(resources, &Self::load_svg(path), width, height, filter)
+    }
+
+    // Rasterizes an SVG at its own size multiplied by the window's scale factor,
+    // so that it stays sharp on HiDPI displays.
+    pub fn from_svg_scaled(resources: &Resources, path: &str, filter: Filter) -> Self {
+        let tree = Self::load_svg(path);
+        let svg_size = tree.size();
+        let scale_factor = resources.scale_factor();
+
+        Self::from_svg_tree(
+            resources,
+            &tree,
+            (svg_size.width() * scale_factor).ceil() as u32,
+            (svg_size.height() * scale_factor).ceil() as u32,
+            filter,
+        )
+    }
+
+    // Data is expected to be tightly packed RGBA with 8 bits per channel.
+    pub fn from_rgba(
+        resources: &Resources,
+        width: u32,
+        height: u32,
+        image_data: &[u8],
+        filter: Filter,
+    ) -> Self {
+        Self::from_rgba_with_usage(resources, width, height, image_data, filter, false)
+    }
+
+    // A transparent texture that compute shaders can also write to as a storage image.
+    // It stays in the general layout so it can be sampled without transitions.
+    pub fn storage(resources: &Resources, width: u32, height: u32, filter: Filter) -> Self {
+        let image_data = vec![0; width as usize * height as usize * 4];
+
+        Self::from_rgba_with_usage(resources, width, height, &image_data, filter, true)
+    }
+
+    fn from_rgba_with_usage(
+        resources: &Resources,
+        width: u32,
+        height: u32,
+        image_data: &[u8],
+        filter: Filter,
+        storage: bool,
+    ) -> Self {
+        let (usage, layout) = if storage {
+            (
+                vk::ImageUsageFlags::TRANSFER_DST
+                    | vk::ImageUsageFlags::SAMPLED
+                    | vk::ImageUsageFlags::STORAGE,
+                vk::ImageLayout::GENERAL,
+            )
+        } else {
+            (
+                vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED,
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            )
+        };
         let image_extent = vk::Extent2D { width, height };
-        let image_data = image.into_raw();
 
         unsafe {
             let image_buffer = buffer::Buffer::new(
-                &image_data,
+                image_data,
                 resources.base.device_data.clone(),
                 vk::BufferUsageFlags::TRANSFER_SRC,
             );
@@ -51,7 +126,7 @@ impl Texture {
                 array_layers: 1,
                 samples: vk::SampleCountFlags::TYPE_1,
                 tiling: vk::ImageTiling::OPTIMAL,
-                usage: vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED,
+                usage,
                 sharing_mode: vk::SharingMode::EXCLUSIVE,
                 ..Default::default()
             };
@@ -117,7 +192,7 @@ impl Texture {
                         src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
                         dst_access_mask: vk::AccessFlags::SHADER_READ,
                         old_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-                        new_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                        new_layout: layout,
                         image: texture_image,
                         subresource_range: vk::ImageSubresourceRange {
                             aspect_mask: vk::ImageAspectFlags::COLOR,
@@ -130,7 +205,8 @@ impl Texture {
                     device.cmd_pipeline_barrier(
                         texture_command_buffer,
                         vk::PipelineStageFlags::TRANSFER,
-                        vk::PipelineStageFlags::FRAGMENT_SHADER,
+                        vk::PipelineStageFlags::FRAGMENT_SHADER
+                            | vk::PipelineStageFlags::COMPUTE_SHADER,
                         vk::DependencyFlags::empty(),
                         &[],
                         &[],
@@ -139,59 +215,143 @@ impl Texture {
                 },
             );
 
-            let sampler_filter = match filter {
-                Filter::Linear => vk::Filter::LINEAR,
-                Filter::Nearest => vk::Filter::NEAREST,
+            let sampler = Self::new_sampler(resources, filter, vk::SamplerAddressMode::REPEAT);
+            let image_view = Self::new_image_view(resources, texture_image, image_info.format);
+
+            let descriptor = vk::DescriptorImageInfo {
+                image_layout: layout,
+                image_view,
+                sampler,
+            };
+
+            Self {
+                device_data: resources.base.device_data.clone(),
+                descriptor,
+                width,
+                height,
+                storage,
+                image_buffer: Some(image_buffer),
+                image_view,
+                allocation: mem::ManuallyDrop::new(allocation),
+                texture_image,
+                sampler,
+            }
+        }
+    }
+
+    // An image the size of the virtual screen, or any other offscreen target, that render
+    // passes draw into and that's sampled afterwards. It has the swapchain's format so the
+    // same pipelines can draw to both, and starts out transparent.
+    pub(super) fn color_attachment(
+        resources: &Resources,
+        width: u32,
+        height: u32,
+        filter: Filter,
+    ) -> Self {
+        let format = resources
+            .base
+            .surface_data
+            .format
+            .expect("Surface format was uninitialized when creating texture")
+            .format;
+        let layout = vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL;
+
+        unsafe {
+            let image_info = vk::ImageCreateInfo {
+                image_type: vk::ImageType::TYPE_2D,
+                format,
+                extent: vk::Extent2D { width, height }.into(),
+                mip_levels: 1,
+                array_layers: 1,
+                samples: vk::SampleCountFlags::TYPE_1,
+                tiling: vk::ImageTiling::OPTIMAL,
+                usage: vk::ImageUsageFlags::COLOR_ATTACHMENT
+                    | vk::ImageUsageFlags::SAMPLED
+                    | vk::ImageUsageFlags::TRANSFER_DST,
+                sharing_mode: vk::SharingMode::EXCLUSIVE,
+                ..Default::default()
             };
 
-            let sampler_info = vk::SamplerCreateInfo {
-                mag_filter: sampler_filter,
-                min_filter: sampler_filter,
-                mipmap_mode: vk::SamplerMipmapMode::LINEAR,
-                address_mode_u: vk::SamplerAddressMode::REPEAT,
-                address_mode_v: vk::SamplerAddressMode::REPEAT,
-                address_mode_w: vk::SamplerAddressMode::REPEAT,
-                max_anisotropy: 1.0,
-                border_color: vk::BorderColor::FLOAT_OPAQUE_WHITE,
-                compare_op: vk::CompareOp::NEVER,
+            let allocation_info = vk_mem::AllocationCreateInfo {
+                usage: vk_mem::MemoryUsage::Auto,
                 ..Default::default()
             };
 
-            let sampler = resources
+            let (texture_image, allocation) = resources
                 .base
                 .device_data
-                .device
-                .create_sampler(&sampler_info, None)
+                .allocator
+                .create_image(&image_info, &allocation_info)
                 .unwrap();
 
-            let image_view_info = vk::ImageViewCreateInfo {
-                view_type: vk::ImageViewType::TYPE_2D,
-                format: image_info.format,
-                components: vk::ComponentMapping {
-                    r: vk::ComponentSwizzle::R,
-                    g: vk::ComponentSwizzle::G,
-                    b: vk::ComponentSwizzle::B,
-                    a: vk::ComponentSwizzle::A,
-                },
-                subresource_range: vk::ImageSubresourceRange {
-                    aspect_mask: vk::ImageAspectFlags::COLOR,
-                    level_count: 1,
-                    layer_count: 1,
-                    ..Default::default()
-                },
-                image: texture_image,
+            let subresource_range = vk::ImageSubresourceRange {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                level_count: 1,
+                layer_count: 1,
                 ..Default::default()
             };
 
-            let image_view = resources
-                .base
-                .device_data
-                .device
-                .create_image_view(&image_view_info, None)
-                .unwrap();
+            // Cleared so that sampling it before anything is drawn is valid.
+            resources.base.device_data.record_submit(
+                resources.base.command_data.setup_buffer,
+                resources.base.sync_data.setup_commands_reuse_fence,
+                &[],
+                &[],
+                &[],
+                |device, texture_command_buffer| {
+                    let texture_barrier = vk::ImageMemoryBarrier {
+                        dst_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+                        new_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                        image: texture_image,
+                        subresource_range,
+                        ..Default::default()
+                    };
+                    device.cmd_pipeline_barrier(
+                        texture_command_buffer,
+                        vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                        vk::PipelineStageFlags::TRANSFER,
+                        vk::DependencyFlags::empty(),
+                        &[],
+                        &[],
+                        &[texture_barrier],
+                    );
+                    device.cmd_clear_color_image(
+                        texture_command_buffer,
+                        texture_image,
+                        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                        &vk::ClearColorValue {
+                            float32: [0.0, 0.0, 0.0, 0.0],
+                        },
+                        &[subresource_range],
+                    );
+                    let texture_barrier_end = vk::ImageMemoryBarrier {
+                        src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+                        dst_access_mask: vk::AccessFlags::SHADER_READ,
+                        old_layout: vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                        new_layout: layout,
+                        image: texture_image,
+                        subresource_range,
+                        ..Default::default()
+                    };
+                    device.cmd_pipeline_barrier(
+                        texture_command_buffer,
+                        vk::PipelineStageFlags::TRANSFER,
+                        vk::PipelineStageFlags::FRAGMENT_SHADER,
+                        vk::DependencyFlags::empty(),
+                        &[],
+                        &[],
+                        &[texture_barrier_end],
+                    );
+                },
+            );
+
+            // Clamped so the edges don't bleed into each other when scaled.
+            let sampler =
+                Self::new_sampler(resources, filter, vk::SamplerAddressMode::CLAMP_TO_EDGE);
+            let image_view = Self::new_image_view(resources, texture_image, format);
 
             let descriptor = vk::DescriptorImageInfo {
-                image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                image_layout: layout,
                 image_view,
                 sampler,
             };
@@ -199,7 +359,10 @@ impl Texture {
             Self {
                 device_data: resources.base.device_data.clone(),
                 descriptor,
-                image_buffer,
+                width,
+                height,
+                storage: false,
+                image_buffer: None,
                 image_view,
                 allocation: mem::ManuallyDrop::new(allocation),
                 texture_image,
@@ -207,6 +370,127 @@ impl Texture {
             }
         }
     }
+
+    pub(super) unsafe fn new_sampler(
+        resources: &Resources,
+        filter: Filter,
+        address_mode: vk::SamplerAddressMode,
+    ) -> vk::Sampler {
+        let sampler_filter = match filter {
+            Filter::Linear => vk::Filter::LINEAR,
+            Filter::Nearest => vk::Filter::NEAREST,
+        };
+
+        let sampler_info = vk::SamplerCreateInfo {
+            mag_filter: sampler_filter,
+            min_filter: sampler_filter,
+            mipmap_mode: vk::SamplerMipmapMode::LINEAR,
+            address_mode_u: address_mode,
+            address_mode_v: address_mode,
+            address_mode_w: address_mode,
+            max_anisotropy: 1.0,
+            border_color: vk::BorderColor::FLOAT_OPAQUE_WHITE,
+            compare_op: vk::CompareOp::NEVER,
+            ..Default::default()
+        };
+
+        resources
+            .base
+            .device_data
+            .device
+            .create_sampler(&sampler_info, None)
+            .unwrap()
+    }
+
+    unsafe fn new_image_view(
+        resources: &Resources,
+        image: vk::Image,
+        format: vk::Format,
+    ) -> vk::ImageView {
+        let image_view_info = vk::ImageViewCreateInfo {
+            view_type: vk::ImageViewType::TYPE_2D,
+            format,
+            components: vk::ComponentMapping {
+                r: vk::ComponentSwizzle::R,
+                g: vk::ComponentSwizzle::G,
+                b: vk::ComponentSwizzle::B,
+                a: vk::ComponentSwizzle::A,
+            },
+            subresource_range: vk::ImageSubresourceRange {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                level_count: 1,
+                layer_count: 1,
+                ..Default::default()
+            },
+            image,
+            ..Default::default()
+        };
+
+        resources
+            .base
+            .device_data
+            .device
+            .create_image_view(&image_view_info, None)
+            .unwrap()
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn is_storage(&self) -> bool {
+        self.storage
+    }
+
+    fn from_svg_tree(
+        resources: &Resources,
+        tree: &usvg::Tree,
+        width: u32,
+        height: u32,
+        filter: Filter,
+    ) -> Self {
+        let svg_size = tree.size();
+
+        let mut pixmap = tiny_skia::Pixmap::new(width.max(1), height.max(1))
+            .expect("Failed to create pixmap for SVG");
+        resvg::render(
+            tree,
+            tiny_skia::Transform::from_scale(
+                pixmap.width() as f32 / svg_size.width(),
+                pixmap.height() as f32 / svg_size.height(),
+            ),
+            &mut pixmap.as_mut(),
+        );
+
+        // Rendered pixels have premultiplied alpha, but textures expect straight alpha like PNGs.
+        let image_data: Vec<u8> = pixmap
+            .pixels()
+            .iter()
+            .flat_map(|pixel| {
+                let color = pixel.demultiply();
+                [color.red(), color.green(), color.blue(), color.alpha()]
+            })
+            .collect();
+
+        Self::from_rgba(
+            resources,
+            pixmap.width(),
+            pixmap.height(),
+            &image_data,
+            filter,
+        )
+    }
+
+    fn load_svg(path: &str) -> usvg::Tree {
+        let data = fs::read(path).unwrap_or_else(|_| panic!("Failed to load file {}", path));
+
+        usvg::Tree::from_data(&data, &usvg::Options::default())
+            .unwrap_or_else(|err| panic!("Failed to parse SVG {}: {}", path, err))
+    }
 }
 
 impl Drop for Texture {
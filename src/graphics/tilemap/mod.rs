@@ -0,0 +1,463 @@
+pub mod tiled;
+
+use std::{mem, rc};
+
+use ash::vk;
+
+use super::{sprite_batch::Vertex, texture, vk_base::*, vk_resources::*, Draw, Resources};
+
+// Width and height of each chunk in tiles.
+const CHUNK_SIZE: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tile {
+    pub id: u32,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+    pub flip_diagonal: bool,
+}
+
+impl Tile {
+    pub fn new(id: u32) -> Self {
+        Self {
+            id,
+            flip_horizontal: false,
+            flip_vertical: false,
+            flip_diagonal: false,
+        }
+    }
+}
+
+// Describes how tiles are laid out in the tileset texture, in pixels.
+#[derive(Clone, Copy, Debug)]
+pub struct Tileset {
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub columns: u32,
+    pub spacing: u32,
+    pub margin: u32,
+}
+
+struct Chunk {
+    index_buffer: Option<buffer::Buffer>,
+    vertex_buffer: Option<buffer::Buffer>,
+    needs_upload: bool,
+}
+
+struct Layer {
+    width: usize,
+    height: usize,
+    z: f32,
+    tiles: Vec<Option<Tile>>,
+    chunk_columns: usize,
+    chunks: Vec<Chunk>,
+}
+
+pub struct Tilemap {
+    device_data: rc::Rc<device_data::DeviceData>,
+
+    layers: Vec<Layer>,
+    tileset: Tileset,
+    tile_width: f32,
+    tile_height: f32,
+    camera_x: f32,
+    camera_y: f32,
+
+    pipeline: pipeline::Pipeline,
+
+    texture: rc::Rc<texture::Texture>,
+}
+
+impl Tilemap {
+    // Tiles are drawn with the given width and height, which don't need to
+    // match the size of tiles in the tileset texture.
+    pub fn new(
+        resources: &Resources,
+        texture: rc::Rc<texture::Texture>,
+        tileset: Tileset,
+        tile_width: f32,
+        tile_height: f32,
+    ) -> Self {
+        let descriptor_layout_bindings = [
+            vk::DescriptorSetLayoutBinding {
+                binding: 1,
+                descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::FRAGMENT,
+                ..Default::default()
+            },
+            vk::DescriptorSetLayoutBinding {
+                binding: 2,
+                descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::VERTEX,
+                ..Default::default()
+            },
+        ];
+        let push_constant_ranges = [vk::PushConstantRange {
+            stage_flags: vk::ShaderStageFlags::VERTEX,
+            offset: 0,
+            size: mem::size_of::<[f32; 2]>() as u32,
+        }];
+
+        let (vertex_input_binding_descriptions, vertex_input_attribute_descriptions) =
+            Vertex::get_info();
+
+        let pipeline = unsafe {
+            pipeline::Pipeline::new(
+                resources,
+                pipeline::PipelineInfo {
+                    vertex_spv: include_bytes!("../../../shader/tilemap.vert.spv"),
+                    fragment_spv: include_bytes!("../../../shader/texture.frag.spv"),
+                    descriptor_layout_bindings: &descriptor_layout_bindings,
+                    vertex_input_binding_descriptions: &vertex_input_binding_descriptions,
+                    vertex_input_attribute_descriptions: &vertex_input_attribute_descriptions,
+                    push_constant_ranges: &push_constant_ranges,
+                    blend: pipeline::Blend::Opaque,
+                    depth_write: true,
+                },
+            )
+        };
+
+        Self {
+            device_data: resources.base.device_data.clone(),
+
+            layers: Vec::new(),
+            tileset,
+            tile_width,
+            tile_height,
+            camera_x: 0.0,
+            camera_y: 0.0,
+
+            pipeline,
+
+            texture,
+        }
+    }
+
+    // Creates a tilemap from the tile layers of a Tiled map, only including
+    // tiles from one of the map's tilesets. Each layer's z is taken from its
+    // "z" property, or its index in the map if the property is missing. Layers
+    // hidden in Tiled, eg: for collision or guides, are left out, so the
+    // tilemap's layers only count the visible ones.
+    pub fn from_tiled(
+        resources: &Resources,
+        map: &tiled::TiledMap,
+        tileset_index: usize,
+        texture: rc::Rc<texture::Texture>,
+    ) -> Self {
+        let tiled_tileset = &map.tilesets[tileset_index];
+
+        let mut tilemap = Self::new(
+            resources,
+            texture,
+            Tileset {
+                tile_width: tiled_tileset.tile_width,
+                tile_height: tiled_tileset.tile_height,
+                columns: tiled_tileset.columns,
+                spacing: tiled_tileset.spacing,
+                margin: tiled_tileset.margin,
+            },
+            map.tile_width as f32,
+            map.tile_height as f32,
+        );
+
+        for (i, tile_layer) in map.tile_layers().enumerate() {
+            if !tile_layer.visible {
+                continue;
+            }
+
+            let z = match tile_layer.properties.get("z") {
+                Some(tiled::PropertyValue::Float(z)) => *z as f32,
+                Some(tiled::PropertyValue::Int(z)) => *z as f32,
+                _ => i as f32,
+            };
+
+            let layer = tilemap.add_layer(tile_layer.width, tile_layer.height, z);
+
+            for y in 0..tile_layer.height {
+                for x in 0..tile_layer.width {
+                    let tile = tile_layer.tiles[x + y * tile_layer.width]
+                        .filter(|tile| tile.tileset == tileset_index)
+                        .map(|tile| tile.tile);
+
+                    tilemap.set_tile(layer, x, y, tile);
+                }
+            }
+        }
+
+        tilemap.upload();
+
+        tilemap
+    }
+
+    // Returns the index of the new layer.
+    pub fn add_layer(&mut self, width: usize, height: usize, z: f32) -> usize {
+        let chunk_columns = width.div_ceil(CHUNK_SIZE);
+        let chunk_rows = height.div_ceil(CHUNK_SIZE);
+
+        self.layers.push(Layer {
+            width,
+            height,
+            z,
+            tiles: vec![None; width * height],
+            chunk_columns,
+            chunks: (0..chunk_columns * chunk_rows)
+                .map(|_| Chunk {
+                    index_buffer: None,
+                    vertex_buffer: None,
+                    needs_upload: false,
+                })
+                .collect(),
+        });
+
+        self.layers.len() - 1
+    }
+
+    pub fn tile(&self, layer: usize, x: usize, y: usize) -> Option<Tile> {
+        let layer = &self.layers[layer];
+
+        if x >= layer.width || y >= layer.height {
+            return None;
+        }
+
+        layer.tiles[x + y * layer.width]
+    }
+
+    // Changes only become visible after the next call to upload.
+    pub fn set_tile(&mut self, layer: usize, x: usize, y: usize, tile: Option<Tile>) {
+        let layer = &mut self.layers[layer];
+
+        if x >= layer.width || y >= layer.height {
+            return;
+        }
+
+        layer.tiles[x + y * layer.width] = tile;
+
+        let chunk_index = x / CHUNK_SIZE + y / CHUNK_SIZE * layer.chunk_columns;
+        layer.chunks[chunk_index].needs_upload = true;
+    }
+
+    // Moves the view so that the given position is at the top left of the screen.
+    pub fn set_camera(&mut self, x: f32, y: f32) {
+        self.camera_x = x;
+        self.camera_y = y;
+    }
+
+    // Rebuilds the buffers of any chunks that have changed.
+    pub fn upload(&mut self) {
+        if self
+            .layers
+            .iter()
+            .all(|layer| layer.chunks.iter().all(|chunk| !chunk.needs_upload))
+        {
+            return;
+        }
+
+        unsafe {
+            self.device_data.device.device_wait_idle().unwrap();
+        }
+
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+
+        for layer_index in 0..self.layers.len() {
+            for chunk_index in 0..self.layers[layer_index].chunks.len() {
+                if !self.layers[layer_index].chunks[chunk_index].needs_upload {
+                    continue;
+                }
+
+                vertices.clear();
+                indices.clear();
+                self.batch_chunk(layer_index, chunk_index, &mut vertices, &mut indices);
+
+                let chunk = &mut self.layers[layer_index].chunks[chunk_index];
+                chunk.needs_upload = false;
+
+                // Buffers can't be initialized with no data, so empty chunks have no buffers.
+                if indices.is_empty() || vertices.is_empty() {
+                    chunk.index_buffer = None;
+                    chunk.vertex_buffer = None;
+                    continue;
+                }
+
+                unsafe {
+                    chunk.index_buffer = Some(buffer::Buffer::new(
+                        &indices,
+                        self.device_data.clone(),
+                        vk::BufferUsageFlags::INDEX_BUFFER,
+                    ));
+
+                    chunk.vertex_buffer = Some(buffer::Buffer::new(
+                        &vertices,
+                        self.device_data.clone(),
+                        vk::BufferUsageFlags::VERTEX_BUFFER,
+                    ));
+                }
+            }
+        }
+    }
+
+    pub fn draw(&self, draw: &Draw) {
+        let (view_width, view_height) = (draw.view_width, draw.view_height);
+        let chunk_width = self.tile_width * CHUNK_SIZE as f32;
+        let chunk_height = self.tile_height * CHUNK_SIZE as f32;
+
+        // Only chunks that overlap the screen need to be drawn.
+        let first_column = (self.camera_x / chunk_width).floor().max(0.0) as usize;
+        let last_column = ((self.camera_x + view_width) / chunk_width).ceil().max(0.0) as usize;
+        let first_row = (self.camera_y / chunk_height).floor().max(0.0) as usize;
+        let last_row = ((self.camera_y + view_height) / chunk_height)
+            .ceil()
+            .max(0.0) as usize;
+
+        unsafe {
+            let descriptor_set = self.pipeline.descriptor_set(draw);
+            let write_descriptor_sets = [
+                vk::WriteDescriptorSet {
+                    dst_set: descriptor_set,
+                    dst_binding: 1,
+                    descriptor_count: 1,
+                    descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                    p_image_info: &self.texture.descriptor,
+                    ..Default::default()
+                },
+                vk::WriteDescriptorSet {
+                    dst_set: descriptor_set,
+                    dst_binding: 2,
+                    descriptor_count: 1,
+                    descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+                    p_buffer_info: &draw.projection_matrix_buffer_descriptor,
+                    ..Default::default()
+                },
+            ];
+
+            draw.device
+                .update_descriptor_sets(&write_descriptor_sets, &[]);
+
+            self.pipeline.bind(draw, descriptor_set);
+
+            let camera = [self.camera_x, self.camera_y];
+            draw.device.cmd_push_constants(
+                draw.command_buffer,
+                self.pipeline.layout(),
+                vk::ShaderStageFlags::VERTEX,
+                0,
+                std::slice::from_raw_parts(camera.as_ptr() as *const u8, mem::size_of_val(&camera)),
+            );
+
+            for layer in &self.layers {
+                let chunk_rows = layer.chunks.len() / layer.chunk_columns.max(1);
+
+                for row in first_row..last_row.min(chunk_rows) {
+                    for column in first_column..last_column.min(layer.chunk_columns) {
+                        let chunk = &layer.chunks[column + row * layer.chunk_columns];
+
+                        let (Some(vertex_buffer), Some(index_buffer)) =
+                            (&chunk.vertex_buffer, &chunk.index_buffer)
+                        else {
+                            continue;
+                        };
+
+                        draw.device.cmd_bind_vertex_buffers(
+                            draw.command_buffer,
+                            0,
+                            &[vertex_buffer.vk_buffer()],
+                            &[0],
+                        );
+                        draw.device.cmd_bind_index_buffer(
+                            draw.command_buffer,
+                            index_buffer.vk_buffer(),
+                            0,
+                            vk::IndexType::UINT32,
+                        );
+                        draw.device.cmd_draw_indexed(
+                            draw.command_buffer,
+                            index_buffer.len() as u32,
+                            1,
+                            0,
+                            0,
+                            1,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn batch_chunk(
+        &self,
+        layer_index: usize,
+        chunk_index: usize,
+        vertices: &mut Vec<Vertex>,
+        indices: &mut Vec<u32>,
+    ) {
+        let layer = &self.layers[layer_index];
+        let start_x = chunk_index % layer.chunk_columns * CHUNK_SIZE;
+        let start_y = chunk_index / layer.chunk_columns * CHUNK_SIZE;
+
+        for y in start_y..(start_y + CHUNK_SIZE).min(layer.height) {
+            for x in start_x..(start_x + CHUNK_SIZE).min(layer.width) {
+                let Some(tile) = layer.tiles[x + y * layer.width] else {
+                    continue;
+                };
+
+                let vertex_count = vertices.len() as u32;
+                let left = x as f32 * self.tile_width;
+                let top = y as f32 * self.tile_height;
+                let corners = [
+                    ([left, top], [0.0, 0.0]),
+                    ([left, top + self.tile_height], [0.0, 1.0]),
+                    ([left + self.tile_width, top + self.tile_height], [1.0, 1.0]),
+                    ([left + self.tile_width, top], [1.0, 0.0]),
+                ];
+
+                for (position, corner) in corners {
+                    vertices.push(Vertex::new(
+                        [position[0], position[1], layer.z],
+                        self.tile_uv(tile, corner),
+                    ));
+                }
+
+                indices.push(vertex_count);
+                indices.push(vertex_count + 1);
+                indices.push(vertex_count + 2);
+                indices.push(vertex_count + 2);
+                indices.push(vertex_count + 3);
+                indices.push(vertex_count);
+            }
+        }
+    }
+
+    // Finds the texture coordinates for a corner of a tile, corners are given
+    // as (0, 0) for the top left through (1, 1) for the bottom right.
+    fn tile_uv(&self, tile: Tile, corner: [f32; 2]) -> [f32; 2] {
+        let [mut u, mut v] = corner;
+
+        // Flips are applied in the same order as Tiled: diagonally (swapping x and y), then
+        // horizontally, then vertically. Since this maps the tile's corners back to the
+        // tileset the order is reversed.
+        if tile.flip_horizontal {
+            u = 1.0 - u;
+        }
+
+        if tile.flip_vertical {
+            v = 1.0 - v;
+        }
+
+        if tile.flip_diagonal {
+            mem::swap(&mut u, &mut v);
+        }
+
+        let columns = self.tileset.columns.max(1);
+        let tile_x = self.tileset.margin
+            + (tile.id % columns) * (self.tileset.tile_width + self.tileset.spacing);
+        let tile_y = self.tileset.margin
+            + (tile.id / columns) * (self.tileset.tile_height + self.tileset.spacing);
+
+        [
+            (tile_x as f32 + u * self.tileset.tile_width as f32) / self.texture.width() as f32,
+            (tile_y as f32 + v * self.tileset.tile_height as f32) / self.texture.height() as f32,
+        ]
+    }
+}
@@ -0,0 +1,686 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use base64::Engine;
+
+use super::Tile;
+use crate::graphics::{color::Color, vec2::Vec2};
+
+const FLIPPED_HORIZONTALLY_FLAG: u32 = 0x80000000;
+const FLIPPED_VERTICALLY_FLAG: u32 = 0x40000000;
+const FLIPPED_DIAGONALLY_FLAG: u32 = 0x20000000;
+const ROTATED_HEXAGONAL_120_FLAG: u32 = 0x10000000;
+const FLAGS: u32 = FLIPPED_HORIZONTALLY_FLAG
+    | FLIPPED_VERTICALLY_FLAG
+    | FLIPPED_DIAGONALLY_FLAG
+    | ROTATED_HEXAGONAL_120_FLAG;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PropertyValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Color(Color),
+    File(String),
+    Object(u32),
+}
+
+pub type Properties = HashMap<String, PropertyValue>;
+
+pub struct TiledTileset {
+    pub first_gid: u32,
+    pub name: String,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub tile_count: u32,
+    pub columns: u32,
+    pub spacing: u32,
+    pub margin: u32,
+    // Path to the tileset's image, relative to the working directory rather than the map.
+    pub image: Option<PathBuf>,
+    pub properties: Properties,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TiledTile {
+    pub tileset: usize,
+    pub tile: Tile,
+}
+
+pub struct TiledTileLayer {
+    pub name: String,
+    pub width: usize,
+    pub height: usize,
+    pub visible: bool,
+    pub tiles: Vec<Option<TiledTile>>,
+    pub properties: Properties,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ObjectShape {
+    Rectangle,
+    Ellipse,
+    Point,
+    // Points are relative to the object's position.
+    Polygon(Vec<Vec2>),
+    Polyline(Vec<Vec2>),
+}
+
+pub struct TiledObject {
+    pub id: u32,
+    pub name: String,
+    pub class: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub rotation: f32,
+    pub visible: bool,
+    pub tile: Option<TiledTile>,
+    pub shape: ObjectShape,
+    pub properties: Properties,
+}
+
+pub struct TiledObjectLayer {
+    pub name: String,
+    pub visible: bool,
+    pub objects: Vec<TiledObject>,
+    pub properties: Properties,
+}
+
+pub enum TiledLayer {
+    Tiles(TiledTileLayer),
+    Objects(TiledObjectLayer),
+}
+
+// Only finite, orthogonal maps are supported. Layers inside of groups are
+// flattened into the map's list of layers, keeping their drawing order.
+pub struct TiledMap {
+    pub width: usize,
+    pub height: usize,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub tilesets: Vec<TiledTileset>,
+    pub layers: Vec<TiledLayer>,
+    pub properties: Properties,
+}
+
+impl TiledMap {
+    // Loads a map saved in either Tiled's XML (.tmx) or JSON (.tmj, .json) format.
+    pub fn load(path: &str) -> Self {
+        let data =
+            fs::read_to_string(path).unwrap_or_else(|_| panic!("Failed to load file {}", path));
+        let directory = Path::new(path).parent().unwrap_or(Path::new(""));
+
+        match Path::new(path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("tmx") => Self::from_tmx(&data, directory),
+            Some("tmj" | "json") => Self::from_tmj(&data, directory),
+            _ => panic!("Unsupported Tiled map format {}", path),
+        }
+    }
+
+    pub fn from_tmx(data: &str, directory: &Path) -> Self {
+        let document = roxmltree::Document::parse(data).expect("Failed to parse Tiled map");
+        let map = document.root_element();
+
+        if attribute(map, "infinite").unwrap_or(0) != 0 {
+            panic!("Infinite Tiled maps are not supported");
+        }
+
+        let tilesets = children(map, "tileset")
+            .map(|tileset| tmx::tileset(tileset, directory))
+            .collect::<Vec<_>>();
+        let mut layers = Vec::new();
+        tmx::layers(map, &tilesets, &mut layers);
+
+        Self {
+            width: required_attribute(map, "width"),
+            height: required_attribute(map, "height"),
+            tile_width: required_attribute(map, "tilewidth"),
+            tile_height: required_attribute(map, "tileheight"),
+            tilesets,
+            layers,
+            properties: tmx::properties(map),
+        }
+    }
+
+    pub fn from_tmj(data: &str, directory: &Path) -> Self {
+        let map: serde_json::Value = serde_json::from_str(data).expect("Failed to parse Tiled map");
+
+        if map["infinite"].as_bool().unwrap_or(false) {
+            panic!("Infinite Tiled maps are not supported");
+        }
+
+        let tilesets = tmj::array(&map["tilesets"])
+            .iter()
+            .map(|tileset| tmj::tileset(tileset, directory))
+            .collect::<Vec<_>>();
+        let mut layers = Vec::new();
+        tmj::layers(&map, &tilesets, &mut layers);
+
+        Self {
+            width: tmj::required_u64(&map, "width") as usize,
+            height: tmj::required_u64(&map, "height") as usize,
+            tile_width: tmj::required_u64(&map, "tilewidth") as u32,
+            tile_height: tmj::required_u64(&map, "tileheight") as u32,
+            tilesets,
+            layers,
+            properties: tmj::properties(&map),
+        }
+    }
+
+    pub fn tile_layers(&self) -> impl Iterator<Item = &TiledTileLayer> {
+        self.layers.iter().filter_map(|layer| match layer {
+            TiledLayer::Tiles(tile_layer) => Some(tile_layer),
+            TiledLayer::Objects(_) => None,
+        })
+    }
+
+    pub fn object_layers(&self) -> impl Iterator<Item = &TiledObjectLayer> {
+        self.layers.iter().filter_map(|layer| match layer {
+            TiledLayer::Tiles(_) => None,
+            TiledLayer::Objects(object_layer) => Some(object_layer),
+        })
+    }
+}
+
+// Global tile ids combine the tileset's first id, the tile's id within the
+// tileset, and flags for how the tile is flipped. Zero means no tile.
+fn tile_from_gid(gid: u32, tilesets: &[TiledTileset]) -> Option<TiledTile> {
+    let id = gid & !FLAGS;
+
+    if id == 0 {
+        return None;
+    }
+
+    let (tileset, first_gid) = tilesets
+        .iter()
+        .enumerate()
+        .filter(|(_, tileset)| tileset.first_gid <= id)
+        .map(|(i, tileset)| (i, tileset.first_gid))
+        .max_by_key(|(_, first_gid)| *first_gid)?;
+
+    Some(TiledTile {
+        tileset,
+        tile: Tile {
+            id: id - first_gid,
+            flip_horizontal: gid & FLIPPED_HORIZONTALLY_FLAG != 0,
+            flip_vertical: gid & FLIPPED_VERTICALLY_FLAG != 0,
+            flip_diagonal: gid & FLIPPED_DIAGONALLY_FLAG != 0,
+        },
+    })
+}
+
+// A layer's tiles row by row, which have to fill it exactly so they can be indexed by
+// position.
+fn tiles_from_gids(
+    gids: Vec<u32>,
+    name: &str,
+    width: usize,
+    height: usize,
+    tilesets: &[TiledTileset],
+) -> Vec<Option<TiledTile>> {
+    if gids.len() != width * height {
+        panic!(
+            "Failed to load Tiled layer {}, its data doesn't match its size: {} tiles for {}x{}",
+            name,
+            gids.len(),
+            width,
+            height
+        );
+    }
+
+    gids.into_iter()
+        .map(|gid| tile_from_gid(gid, tilesets))
+        .collect()
+}
+
+fn decode_tile_data(data: &str, encoding: &str, compression: Option<&str>) -> Vec<u32> {
+    match encoding {
+        "csv" => data
+            .split(',')
+            .map(|gid| gid.trim().parse().expect("Invalid tile in CSV layer data"))
+            .collect(),
+        "base64" => {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(data.trim())
+                .expect("Invalid base64 layer data");
+
+            let mut decompressed = Vec::new();
+            match compression {
+                None | Some("") => decompressed = bytes,
+                Some("zlib") => {
+                    flate2::read::ZlibDecoder::new(&bytes[..])
+                        .read_to_end(&mut decompressed)
+                        .expect("Invalid zlib layer data");
+                }
+                Some("gzip") => {
+                    flate2::read::GzDecoder::new(&bytes[..])
+                        .read_to_end(&mut decompressed)
+                        .expect("Invalid gzip layer data");
+                }
+                Some(compression) => panic!("Unsupported layer compression {}", compression),
+            }
+
+            decompressed
+                .chunks_exact(4)
+                .map(|gid| u32::from_le_bytes([gid[0], gid[1], gid[2], gid[3]]))
+                .collect()
+        }
+        _ => panic!("Unsupported layer encoding {}", encoding),
+    }
+}
+
+fn parse_color(color: &str) -> Color {
+    let hex = color.trim_start_matches('#');
+    let channel = |i: usize| {
+        u8::from_str_radix(&hex[i..i + 2], 16).expect("Invalid color property") as f32 / 255.0
+    };
+
+    // Tiled stores colors as either #RRGGBB or #AARRGGBB.
+    match hex.len() {
+        6 => Color::rgb(channel(0), channel(2), channel(4)),
+        8 => Color::new(channel(2), channel(4), channel(6), channel(0)),
+        _ => panic!("Invalid color property {}", color),
+    }
+}
+
+fn children<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = roxmltree::Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| child.has_tag_name(name))
+}
+
+fn attribute<T: std::str::FromStr>(node: roxmltree::Node, name: &str) -> Option<T> {
+    node.attribute(name).and_then(|value| value.parse().ok())
+}
+
+fn required_attribute<T: std::str::FromStr>(node: roxmltree::Node, name: &str) -> T {
+    attribute(node, name).unwrap_or_else(|| {
+        panic!(
+            "Missing or invalid attribute {} on Tiled element {}",
+            name,
+            node.tag_name().name()
+        )
+    })
+}
+
+mod tmx {
+    use super::*;
+
+    pub fn tileset(node: roxmltree::Node, directory: &Path) -> TiledTileset {
+        let first_gid = required_attribute(node, "firstgid");
+
+        // External tilesets keep everything but their first id in a separate file.
+        if let Some(source) = node.attribute("source") {
+            let source = directory.join(source);
+            let data = fs::read_to_string(&source)
+                .unwrap_or_else(|_| panic!("Failed to load file {}", source.display()));
+            let directory = source.parent().unwrap_or(Path::new(""));
+
+            return if source.extension().and_then(|extension| extension.to_str()) == Some("tsx") {
+                let document =
+                    roxmltree::Document::parse(&data).expect("Failed to parse Tiled tileset");
+                tileset_contents(document.root_element(), first_gid, directory)
+            } else {
+                let tileset: serde_json::Value =
+                    serde_json::from_str(&data).expect("Failed to parse Tiled tileset");
+                tmj::tileset_contents(&tileset, first_gid, directory)
+            };
+        }
+
+        tileset_contents(node, first_gid, directory)
+    }
+
+    pub fn tileset_contents(
+        node: roxmltree::Node,
+        first_gid: u32,
+        directory: &Path,
+    ) -> TiledTileset {
+        TiledTileset {
+            first_gid,
+            name: node.attribute("name").unwrap_or_default().to_string(),
+            tile_width: required_attribute(node, "tilewidth"),
+            tile_height: required_attribute(node, "tileheight"),
+            tile_count: attribute(node, "tilecount").unwrap_or(0),
+            columns: attribute(node, "columns").unwrap_or(0),
+            spacing: attribute(node, "spacing").unwrap_or(0),
+            margin: attribute(node, "margin").unwrap_or(0),
+            image: children(node, "image")
+                .next()
+                .and_then(|image| image.attribute("source"))
+                .map(|source| directory.join(source)),
+            properties: properties(node),
+        }
+    }
+
+    pub fn layers(node: roxmltree::Node, tilesets: &[TiledTileset], layers: &mut Vec<TiledLayer>) {
+        for child in node.children() {
+            match child.tag_name().name() {
+                "layer" => layers.push(TiledLayer::Tiles(tile_layer(child, tilesets))),
+                "objectgroup" => layers.push(TiledLayer::Objects(object_layer(child, tilesets))),
+                "group" => self::layers(child, tilesets, layers),
+                _ => {}
+            }
+        }
+    }
+
+    fn tile_layer(node: roxmltree::Node, tilesets: &[TiledTileset]) -> TiledTileLayer {
+        let data = children(node, "data")
+            .next()
+            .expect("Tiled layer is missing its data");
+
+        let gids: Vec<u32> = match data.attribute("encoding") {
+            Some(encoding) => decode_tile_data(
+                data.text().unwrap_or_default(),
+                encoding,
+                data.attribute("compression"),
+            ),
+            None => children(data, "tile")
+                .map(|tile| attribute(tile, "gid").unwrap_or(0))
+                .collect(),
+        };
+
+        let name = node.attribute("name").unwrap_or_default().to_string();
+        let width = required_attribute(node, "width");
+        let height = required_attribute(node, "height");
+
+        TiledTileLayer {
+            tiles: tiles_from_gids(gids, &name, width, height, tilesets),
+            name,
+            width,
+            height,
+            visible: attribute(node, "visible").unwrap_or(1) != 0,
+            properties: properties(node),
+        }
+    }
+
+    fn object_layer(node: roxmltree::Node, tilesets: &[TiledTileset]) -> TiledObjectLayer {
+        TiledObjectLayer {
+            name: node.attribute("name").unwrap_or_default().to_string(),
+            visible: attribute(node, "visible").unwrap_or(1) != 0,
+            objects: children(node, "object")
+                .map(|object| self::object(object, tilesets))
+                .collect(),
+            properties: properties(node),
+        }
+    }
+
+    fn object(node: roxmltree::Node, tilesets: &[TiledTileset]) -> TiledObject {
+        let mut shape = ObjectShape::Rectangle;
+
+        for child in node.children() {
+            shape = match child.tag_name().name() {
+                "ellipse" => ObjectShape::Ellipse,
+                "point" => ObjectShape::Point,
+                "polygon" => ObjectShape::Polygon(points(child)),
+                "polyline" => ObjectShape::Polyline(points(child)),
+                _ => continue,
+            };
+        }
+
+        TiledObject {
+            id: attribute(node, "id").unwrap_or(0),
+            name: node.attribute("name").unwrap_or_default().to_string(),
+            // Tiled 1.9 renamed the object's "type" to "class".
+            class: node
+                .attribute("class")
+                .or(node.attribute("type"))
+                .unwrap_or_default()
+                .to_string(),
+            x: attribute(node, "x").unwrap_or(0.0),
+            y: attribute(node, "y").unwrap_or(0.0),
+            width: attribute(node, "width").unwrap_or(0.0),
+            height: attribute(node, "height").unwrap_or(0.0),
+            rotation: attribute(node, "rotation").unwrap_or(0.0),
+            visible: attribute(node, "visible").unwrap_or(1) != 0,
+            tile: attribute(node, "gid").and_then(|gid| tile_from_gid(gid, tilesets)),
+            shape,
+            properties: properties(node),
+        }
+    }
+
+    fn points(node: roxmltree::Node) -> Vec<Vec2> {
+        node.attribute("points")
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(|point| {
+                let (x, y) = point.split_once(',').expect("Invalid object point");
+                [
+                    x.parse().expect("Invalid object point"),
+                    y.parse().expect("Invalid object point"),
+                ]
+            })
+            .collect()
+    }
+
+    pub fn properties(node: roxmltree::Node) -> Properties {
+        let mut properties = Properties::new();
+
+        for property in children(node, "properties").flat_map(|node| children(node, "property")) {
+            let name = required_attribute::<String>(property, "name");
+            // Multi-line strings are stored as the property's text instead of its value.
+            let value = property
+                .attribute("value")
+                .or(property.text())
+                .unwrap_or_default();
+
+            let value = match property.attribute("type").unwrap_or("string") {
+                "bool" => PropertyValue::Bool(value == "true"),
+                "int" => PropertyValue::Int(value.parse().expect("Invalid int property")),
+                "float" => PropertyValue::Float(value.parse().expect("Invalid float property")),
+                "color" if value.is_empty() => PropertyValue::Color(Color::TRANSPARENT),
+                "color" => PropertyValue::Color(parse_color(value)),
+                "file" => PropertyValue::File(value.to_string()),
+                "object" => PropertyValue::Object(value.parse().expect("Invalid object property")),
+                // Class properties contain nested properties, which aren't supported.
+                "class" => continue,
+                _ => PropertyValue::String(value.to_string()),
+            };
+
+            properties.insert(name, value);
+        }
+
+        properties
+    }
+}
+
+mod tmj {
+    use serde_json::Value;
+
+    use super::*;
+
+    pub fn array(value: &Value) -> &[Value] {
+        value.as_array().map(|array| &array[..]).unwrap_or(&[])
+    }
+
+    pub fn required_u64(value: &Value, name: &str) -> u64 {
+        value[name]
+            .as_u64()
+            .unwrap_or_else(|| panic!("Missing or invalid Tiled field {}", name))
+    }
+
+    fn string(value: &Value, name: &str) -> String {
+        value[name].as_str().unwrap_or_default().to_string()
+    }
+
+    fn f32(value: &Value, name: &str) -> f32 {
+        value[name].as_f64().unwrap_or(0.0) as f32
+    }
+
+    fn u32(value: &Value, name: &str) -> u32 {
+        value[name].as_u64().unwrap_or(0) as u32
+    }
+
+    pub fn tileset(value: &Value, directory: &Path) -> TiledTileset {
+        let first_gid = required_u64(value, "firstgid") as u32;
+
+        // External tilesets keep everything but their first id in a separate file.
+        if let Some(source) = value["source"].as_str() {
+            let source = directory.join(source);
+            let data = fs::read_to_string(&source)
+                .unwrap_or_else(|_| panic!("Failed to load file {}", source.display()));
+            let directory = source.parent().unwrap_or(Path::new(""));
+
+            return if source.extension().and_then(|extension| extension.to_str()) == Some("tsx") {
+                let document =
+                    roxmltree::Document::parse(&data).expect("Failed to parse Tiled tileset");
+                tmx::tileset_contents(document.root_element(), first_gid, directory)
+            } else {
+                let tileset: Value =
+                    serde_json::from_str(&data).expect("Failed to parse Tiled tileset");
+                tileset_contents(&tileset, first_gid, directory)
+            };
+        }
+
+        tileset_contents(value, first_gid, directory)
+    }
+
+    pub fn tileset_contents(value: &Value, first_gid: u32, directory: &Path) -> TiledTileset {
+        TiledTileset {
+            first_gid,
+            name: string(value, "name"),
+            tile_width: required_u64(value, "tilewidth") as u32,
+            tile_height: required_u64(value, "tileheight") as u32,
+            tile_count: u32(value, "tilecount"),
+            columns: u32(value, "columns"),
+            spacing: u32(value, "spacing"),
+            margin: u32(value, "margin"),
+            image: value["image"].as_str().map(|image| directory.join(image)),
+            properties: properties(value),
+        }
+    }
+
+    pub fn layers(value: &Value, tilesets: &[TiledTileset], layers: &mut Vec<TiledLayer>) {
+        for layer in array(&value["layers"]) {
+            match layer["type"].as_str() {
+                Some("tilelayer") => layers.push(TiledLayer::Tiles(tile_layer(layer, tilesets))),
+                Some("objectgroup") => {
+                    layers.push(TiledLayer::Objects(object_layer(layer, tilesets)))
+                }
+                Some("group") => self::layers(layer, tilesets, layers),
+                _ => {}
+            }
+        }
+    }
+
+    fn tile_layer(value: &Value, tilesets: &[TiledTileset]) -> TiledTileLayer {
+        let gids: Vec<u32> = match &value["data"] {
+            Value::String(data) => decode_tile_data(
+                data,
+                value["encoding"].as_str().unwrap_or("base64"),
+                value["compression"].as_str(),
+            ),
+            data => array(data)
+                .iter()
+                .map(|gid| gid.as_u64().unwrap_or(0) as u32)
+                .collect(),
+        };
+
+        let name = string(value, "name");
+        let width = required_u64(value, "width") as usize;
+        let height = required_u64(value, "height") as usize;
+
+        TiledTileLayer {
+            tiles: tiles_from_gids(gids, &name, width, height, tilesets),
+            name,
+            width,
+            height,
+            visible: value["visible"].as_bool().unwrap_or(true),
+            properties: properties(value),
+        }
+    }
+
+    fn object_layer(value: &Value, tilesets: &[TiledTileset]) -> TiledObjectLayer {
+        TiledObjectLayer {
+            name: string(value, "name"),
+            visible: value["visible"].as_bool().unwrap_or(true),
+            objects: array(&value["objects"])
+                .iter()
+                .map(|object| self::object(object, tilesets))
+                .collect(),
+            properties: properties(value),
+        }
+    }
+
+    fn object(value: &Value, tilesets: &[TiledTileset]) -> TiledObject {
+        let shape = if value["ellipse"].as_bool().unwrap_or(false) {
+            ObjectShape::Ellipse
+        } else if value["point"].as_bool().unwrap_or(false) {
+            ObjectShape::Point
+        } else if value["polygon"].is_array() {
+            ObjectShape::Polygon(points(&value["polygon"]))
+        } else if value["polyline"].is_array() {
+            ObjectShape::Polyline(points(&value["polyline"]))
+        } else {
+            ObjectShape::Rectangle
+        };
+
+        TiledObject {
+            id: u32(value, "id"),
+            name: string(value, "name"),
+            // Tiled 1.9 renamed the object's "type" to "class".
+            class: value["class"]
+                .as_str()
+                .or(value["type"].as_str())
+                .unwrap_or_default()
+                .to_string(),
+            x: f32(value, "x"),
+            y: f32(value, "y"),
+            width: f32(value, "width"),
+            height: f32(value, "height"),
+            rotation: f32(value, "rotation"),
+            visible: value["visible"].as_bool().unwrap_or(true),
+            tile: value["gid"]
+                .as_u64()
+                .and_then(|gid| tile_from_gid(gid as u32, tilesets)),
+            shape,
+            properties: properties(value),
+        }
+    }
+
+    fn points(value: &Value) -> Vec<Vec2> {
+        array(value)
+            .iter()
+            .map(|point| [f32(point, "x"), f32(point, "y")])
+            .collect()
+    }
+
+    pub fn properties(value: &Value) -> Properties {
+        let mut properties = Properties::new();
+
+        for property in array(&value["properties"]) {
+            let name = string(property, "name");
+            let value = &property["value"];
+
+            let value = match property["type"].as_str().unwrap_or("string") {
+                "bool" => PropertyValue::Bool(value.as_bool().unwrap_or(false)),
+                "int" => PropertyValue::Int(value.as_i64().expect("Invalid int property")),
+                "float" => PropertyValue::Float(value.as_f64().expect("Invalid float property")),
+                "color" => match value.as_str() {
+                    Some("") | None => PropertyValue::Color(Color::TRANSPARENT),
+                    Some(color) => PropertyValue::Color(parse_color(color)),
+                },
+                "file" => PropertyValue::File(value.as_str().unwrap_or_default().to_string()),
+                "object" => PropertyValue::Object(value.as_u64().unwrap_or(0) as u32),
+                // Class properties contain nested properties, which aren't supported.
+                "class" => continue,
+                _ => PropertyValue::String(value.as_str().unwrap_or_default().to_string()),
+            };
+
+            properties.insert(name, value);
+        }
+
+        properties
+    }
+}
@@ -0,0 +1,43 @@
+pub type Vec2 = [f32; 2];
+
+pub fn add(a: Vec2, b: Vec2) -> Vec2 {
+    [a[0] + b[0], a[1] + b[1]]
+}
+
+pub fn sub(a: Vec2, b: Vec2) -> Vec2 {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+pub fn scale(a: Vec2, factor: f32) -> Vec2 {
+    [a[0] * factor, a[1] * factor]
+}
+
+pub fn dot(a: Vec2, b: Vec2) -> f32 {
+    a[0] * b[0] + a[1] * b[1]
+}
+
+pub fn cross(a: Vec2, b: Vec2) -> f32 {
+    a[0] * b[1] - a[1] * b[0]
+}
+
+pub fn distance(a: Vec2, b: Vec2) -> f32 {
+    let difference = sub(b, a);
+
+    dot(difference, difference).sqrt()
+}
+
+pub fn normalize(a: Vec2) -> Option<Vec2> {
+    let length = dot(a, a).sqrt();
+
+    if length < f32::EPSILON {
+        return None;
+    }
+
+    Some(scale(a, 1.0 / length))
+}
+
+pub fn segment_normal(start: Vec2, end: Vec2) -> Option<Vec2> {
+    let direction = normalize(sub(end, start))?;
+
+    Some([-direction[1], direction[0]])
+}
@@ -0,0 +1,100 @@
+use super::{post_process::pass, render_target, texture, Draw, Resources};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scaling {
+    // Scales by whole numbers only so every virtual pixel is the same size, with black bars
+    // filling the rest of the window. Windows smaller than the virtual screen fall back to Fit.
+    Integer,
+    // Scales as large as fits while keeping the aspect ratio, so some virtual pixels end up
+    // a window pixel wider than others.
+    Fit,
+}
+
+// Where the virtual screen is drawn in the window, in physical pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    // How many physical pixels make up one virtual pixel.
+    pub scale: f32,
+}
+
+// The app draws into this at a fixed size, which is then upscaled to the window.
+pub(super) struct VirtualScreen {
+    scaling: Scaling,
+
+    render_target: render_target::RenderTarget,
+    present: pass::Pass,
+}
+
+impl VirtualScreen {
+    pub fn new(resources: &Resources, width: u32, height: u32, scaling: Scaling) -> Self {
+        let render_target = render_target::RenderTarget::new(
+            resources,
+            width,
+            height,
+            texture::Filter::Nearest,
+            true,
+        );
+
+        Self {
+            scaling,
+
+            render_target,
+            present: pass::Pass::new(
+                resources,
+                include_bytes!("../../shader/copy.frag.spv"),
+                texture::Filter::Nearest,
+            ),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.render_target.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.render_target.height()
+    }
+
+    pub fn scaling(&self) -> Scaling {
+        self.scaling
+    }
+
+    pub fn set_scaling(&mut self, scaling: Scaling) {
+        self.scaling = scaling;
+    }
+
+    // Centered in a window of the given size, in physical pixels.
+    pub fn viewport(&self, window_width: u32, window_height: u32) -> Viewport {
+        let (window_width, window_height) = (window_width as f32, window_height as f32);
+        let (width, height) = (self.width() as f32, self.height() as f32);
+
+        let fit_scale = (window_width / width).min(window_height / height);
+        let scale = match self.scaling {
+            Scaling::Integer if fit_scale >= 1.0 => fit_scale.floor(),
+            _ => fit_scale,
+        };
+
+        Viewport {
+            x: ((window_width - width * scale) / 2.0).floor(),
+            y: ((window_height - height * scale) / 2.0).floor(),
+            width: width * scale,
+            height: height * scale,
+            scale,
+        }
+    }
+
+    pub fn render_target(&self) -> &render_target::RenderTarget {
+        &self.render_target
+    }
+
+    // Draws what the app drew, or the result of post processing it, expecting the draw's
+    // viewport to be where it goes in the window.
+    pub fn draw(&self, draw: &Draw, texture: &texture::Texture) {
+        self.present
+            .draw(draw, texture, texture, &pass::PassConstants::default());
+    }
+}
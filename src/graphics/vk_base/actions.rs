@@ -0,0 +1,216 @@
+use std::{collections::BTreeMap, fmt, fs, io};
+
+use serde::{Deserialize, Serialize};
+use winit::event::{MouseButton, VirtualKeyCode};
+
+use super::{gamepad::*, input::Input, physical_key::PhysicalKey};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputButton {
+    Key(VirtualKeyCode),
+    PhysicalKey(PhysicalKey),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputAxis {
+    MouseDeltaX,
+    MouseDeltaY,
+    ScrollLinesX,
+    ScrollLinesY,
+    ScrollPixelsX,
+    ScrollPixelsY,
+    Gamepad(GamepadAxis),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionBinding {
+    Button(InputButton),
+    // -1 while only the negative button is held, 1 while only the positive one is.
+    Composite {
+        negative: InputButton,
+        positive: InputButton,
+    },
+    Axis {
+        axis: InputAxis,
+        scale: f32,
+    },
+}
+
+#[derive(Debug)]
+pub enum ActionMapError {
+    // eg: the file doesn't exist yet on the first run.
+    Io(io::Error),
+    // eg: a typo, or a binding renamed since the file was saved.
+    Parse(serde_json::Error),
+}
+
+impl fmt::Display for ActionMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActionMapError::Io(error) => write!(f, "Failed to load action map: {}", error),
+            ActionMapError::Parse(error) => write!(f, "Failed to parse action map: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for ActionMapError {}
+
+// Named actions, each bound to any number of inputs. Querying an action nothing
+// is bound to, including one that doesn't exist, behaves as if it's untouched.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ActionMap {
+    actions: BTreeMap<String, Vec<ActionBinding>>,
+    // Not saved, since which gamepad a player uses changes between sessions.
+    #[serde(skip)]
+    gamepad: Option<GamepadId>,
+}
+
+impl ActionMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Rebinding files are edited by players and kept between versions, so a missing or
+    // broken one is returned as an error for the game to fall back to its defaults.
+    pub fn load(path: &str) -> Result<Self, ActionMapError> {
+        let data = fs::read_to_string(path).map_err(ActionMapError::Io)?;
+
+        Self::from_json(&data).map_err(ActionMapError::Parse)
+    }
+
+    pub fn save(&self, path: &str) {
+        fs::write(path, self.to_json()).unwrap_or_else(|_| panic!("Failed to save file {}", path));
+    }
+
+    pub fn from_json(data: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(data)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn bind(&mut self, action: &str, binding: ActionBinding) {
+        let bindings = self.actions.entry(action.to_string()).or_default();
+
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn unbind(&mut self, action: &str, binding: ActionBinding) {
+        if let Some(bindings) = self.actions.get_mut(action) {
+            bindings.retain(|&other| other != binding);
+        }
+    }
+
+    // Replaces every binding of an action, eg: after a player picks a new key for it.
+    pub fn set_bindings(&mut self, action: &str, bindings: Vec<ActionBinding>) {
+        self.actions.insert(action.to_string(), bindings);
+    }
+
+    pub fn bindings(&self, action: &str) -> &[ActionBinding] {
+        self.actions
+            .get(action)
+            .map(|bindings| &bindings[..])
+            .unwrap_or(&[])
+    }
+
+    pub fn actions(&self) -> impl Iterator<Item = &str> {
+        self.actions.keys().map(|action| &action[..])
+    }
+
+    pub fn gamepad(&self) -> Option<GamepadId> {
+        self.gamepad
+    }
+
+    // Limits gamepad bindings to one gamepad, eg: giving each local player their
+    // own copy of the map. With None, every connected gamepad is read.
+    pub fn set_gamepad(&mut self, gamepad: Option<GamepadId>) {
+        self.gamepad = gamepad;
+    }
+
+    pub fn is_held(&self, input: &Input, action: &str) -> bool {
+        self.bindings(action).iter().any(|binding| match *binding {
+            ActionBinding::Button(button) => self.is_button_held(input, button),
+            ActionBinding::Composite { negative, positive } => {
+                self.is_button_held(input, negative) || self.is_button_held(input, positive)
+            }
+            ActionBinding::Axis { axis, scale } => self.axis_value(input, axis) * scale != 0.0,
+        })
+    }
+
+    pub fn was_pressed(&self, input: &Input, action: &str) -> bool {
+        self.any_button(action, |button| match (button, self.gamepad) {
+            (InputButton::Gamepad(button), Some(id)) => {
+                input.was_gamepad_button_pressed(id, button)
+            }
+            _ => input.was_button_pressed(button),
+        })
+    }
+
+    pub fn was_released(&self, input: &Input, action: &str) -> bool {
+        self.any_button(action, |button| match (button, self.gamepad) {
+            (InputButton::Gamepad(button), Some(id)) => {
+                input.was_gamepad_button_released(id, button)
+            }
+            _ => input.was_button_released(button),
+        })
+    }
+
+    // The value of whichever binding is furthest from zero, buttons being 0 or 1.
+    pub fn value(&self, input: &Input, action: &str) -> f32 {
+        let button_value = |button| {
+            if self.is_button_held(input, button) {
+                1.0
+            } else {
+                0.0
+            }
+        };
+
+        self.bindings(action)
+            .iter()
+            .map(|binding| match *binding {
+                ActionBinding::Button(button) => button_value(button),
+                ActionBinding::Composite { negative, positive } => {
+                    button_value(positive) - button_value(negative)
+                }
+                ActionBinding::Axis { axis, scale } => self.axis_value(input, axis) * scale,
+            })
+            .fold(0.0, |value: f32, binding_value: f32| {
+                if binding_value.abs() > value.abs() {
+                    binding_value
+                } else {
+                    value
+                }
+            })
+    }
+
+    fn is_button_held(&self, input: &Input, button: InputButton) -> bool {
+        match (button, self.gamepad) {
+            (InputButton::Gamepad(button), Some(id)) => input.is_gamepad_button_held(id, button),
+            _ => input.is_button_held(button),
+        }
+    }
+
+    fn axis_value(&self, input: &Input, axis: InputAxis) -> f32 {
+        match (axis, self.gamepad) {
+            (InputAxis::Gamepad(axis), Some(id)) => input.gamepad_axis(id, axis),
+            _ => input.axis_value(axis),
+        }
+    }
+
+    fn any_button<F: Fn(InputButton) -> bool>(&self, action: &str, f: F) -> bool {
+        self.bindings(action).iter().any(|binding| match *binding {
+            ActionBinding::Button(button) => f(button),
+            ActionBinding::Composite { negative, positive } => f(negative) || f(positive),
+            ActionBinding::Axis { .. } => false,
+        })
+    }
+}
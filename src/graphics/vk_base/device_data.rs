@@ -28,17 +28,18 @@ impl DeviceData {
                     .iter()
                     .enumerate()
                     .find_map(|(index, info)| {
-                        let supports_graphic_and_surface =
-                            info.queue_flags.contains(vk::QueueFlags::GRAPHICS)
-                                && surface_data
-                                    .loader
-                                    .get_physical_device_surface_support(
-                                        *physical_device,
-                                        index as u32,
-                                        surface_data.surface,
-                                    )
-                                    .unwrap();
-                        if supports_graphic_and_surface {
+                        let supports_graphic_compute_and_surface = info
+                            .queue_flags
+                            .contains(vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE)
+                            && surface_data
+                                .loader
+                                .get_physical_device_surface_support(
+                                    *physical_device,
+                                    index as u32,
+                                    surface_data.surface,
+                                )
+                                .unwrap();
+                        if supports_graphic_compute_and_surface {
                             Some((*physical_device, index))
                         } else {
                             None
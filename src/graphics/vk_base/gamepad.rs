@@ -0,0 +1,304 @@
+use std::{cell, collections::VecDeque, rc};
+
+use serde::{Deserialize, Serialize};
+use winit::event::ElementState;
+
+pub use gilrs::{Axis as GamepadAxis, Button as GamepadButton};
+
+use super::input::InputEvent;
+
+// Stays the same while a gamepad is connected, and usually after it's reconnected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct GamepadId(pub usize);
+
+// Where Input gets gamepad events from, polled once per frame.
+pub trait GamepadBackend {
+    fn next_event(&mut self) -> Option<InputEvent>;
+}
+
+// Real gamepads. If they can't be accessed, eg: without permission to the
+// devices, it prints why and acts like none are connected.
+pub struct GilrsBackend {
+    gilrs: Option<gilrs::Gilrs>,
+    // Gamepads plugged in before startup are connected with the first events.
+    pending: VecDeque<InputEvent>,
+}
+
+impl GilrsBackend {
+    pub fn new() -> Self {
+        let gilrs = match gilrs::Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(error) => {
+                eprintln!("Failed to initialize gamepads: {}", error);
+                None
+            }
+        };
+
+        let pending = gilrs
+            .iter()
+            .flat_map(|gilrs| gilrs.gamepads())
+            .map(|(id, gamepad)| InputEvent::GamepadConnected {
+                id: GamepadId(id.into()),
+                name: gamepad.name().to_string(),
+            })
+            .collect();
+
+        Self { gilrs, pending }
+    }
+}
+
+impl GamepadBackend for GilrsBackend {
+    fn next_event(&mut self) -> Option<InputEvent> {
+        if let Some(event) = self.pending.pop_front() {
+            return Some(event);
+        }
+
+        let gilrs = self.gilrs.as_mut()?;
+
+        // Skip events with nothing for Input, rather than returning None early.
+        while let Some(gilrs::Event {
+            id: gilrs_id,
+            event,
+            ..
+        }) = gilrs.next_event()
+        {
+            let id = GamepadId(gilrs_id.into());
+
+            let event = match event {
+                gilrs::EventType::ButtonPressed(button, _) => InputEvent::GamepadButton {
+                    id,
+                    button,
+                    state: ElementState::Pressed,
+                },
+                gilrs::EventType::ButtonReleased(button, _) => InputEvent::GamepadButton {
+                    id,
+                    button,
+                    state: ElementState::Released,
+                },
+                gilrs::EventType::ButtonChanged(button, value, _) => {
+                    InputEvent::GamepadButtonValue { id, button, value }
+                }
+                gilrs::EventType::AxisChanged(axis, value, _) => {
+                    InputEvent::GamepadAxis { id, axis, value }
+                }
+                gilrs::EventType::Connected => InputEvent::GamepadConnected {
+                    id,
+                    name: gilrs.gamepad(gilrs_id).name().to_string(),
+                },
+                gilrs::EventType::Disconnected => InputEvent::GamepadDisconnected { id },
+                gilrs::EventType::ButtonRepeated(..) | gilrs::EventType::Dropped => continue,
+            };
+
+            return Some(event);
+        }
+
+        None
+    }
+}
+
+// Gamepads driven from code, eg: by tests on machines without any plugged in.
+// Clones share the same gamepads, so keep one to drive the backend given to Input.
+#[derive(Clone, Default)]
+pub struct VirtualGamepads {
+    events: rc::Rc<cell::RefCell<VecDeque<InputEvent>>>,
+    next_id: rc::Rc<cell::Cell<usize>>,
+}
+
+impl VirtualGamepads {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn connect(&self, name: &str) -> GamepadId {
+        let id = GamepadId(self.next_id.get());
+        self.next_id.set(id.0 + 1);
+
+        self.push(InputEvent::GamepadConnected {
+            id,
+            name: name.to_string(),
+        });
+
+        id
+    }
+
+    pub fn disconnect(&self, id: GamepadId) {
+        self.push(InputEvent::GamepadDisconnected { id });
+    }
+
+    pub fn press(&self, id: GamepadId, button: GamepadButton) {
+        self.push(InputEvent::GamepadButtonValue {
+            id,
+            button,
+            value: 1.0,
+        });
+        self.push(InputEvent::GamepadButton {
+            id,
+            button,
+            state: ElementState::Pressed,
+        });
+    }
+
+    pub fn release(&self, id: GamepadId, button: GamepadButton) {
+        self.push(InputEvent::GamepadButtonValue {
+            id,
+            button,
+            value: 0.0,
+        });
+        self.push(InputEvent::GamepadButton {
+            id,
+            button,
+            state: ElementState::Released,
+        });
+    }
+
+    pub fn set_axis(&self, id: GamepadId, axis: GamepadAxis, value: f32) {
+        self.push(InputEvent::GamepadAxis { id, axis, value });
+    }
+
+    fn push(&self, event: InputEvent) {
+        self.events.borrow_mut().push_back(event);
+    }
+}
+
+impl GamepadBackend for VirtualGamepads {
+    fn next_event(&mut self) -> Option<InputEvent> {
+        self.events.borrow_mut().pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::actions::{ActionBinding, ActionMap, InputAxis, InputButton};
+    use super::super::input::Input;
+    use super::*;
+
+    fn virtual_input() -> (VirtualGamepads, Input) {
+        let gamepads = VirtualGamepads::new();
+        let input = Input::with_gamepad_backend(Box::new(gamepads.clone()));
+        (gamepads, input)
+    }
+
+    #[test]
+    fn press_and_release() {
+        let (gamepads, mut input) = virtual_input();
+        let id = gamepads.connect("Pad");
+
+        gamepads.press(id, GamepadButton::South);
+        input.poll_gamepads();
+        assert!(input.was_gamepad_button_pressed(id, GamepadButton::South));
+        assert!(input.is_gamepad_button_held(id, GamepadButton::South));
+        assert_eq!(input.gamepad_button_value(id, GamepadButton::South), 1.0);
+        assert!(!input.was_gamepad_button_pressed(id, GamepadButton::East));
+
+        input.update();
+        input.poll_gamepads();
+        assert!(!input.was_gamepad_button_pressed(id, GamepadButton::South));
+        assert!(input.is_gamepad_button_held(id, GamepadButton::South));
+
+        gamepads.release(id, GamepadButton::South);
+        input.update();
+        input.poll_gamepads();
+        assert!(input.was_gamepad_button_released(id, GamepadButton::South));
+        assert!(!input.is_gamepad_button_held(id, GamepadButton::South));
+        assert_eq!(input.gamepad_button_value(id, GamepadButton::South), 0.0);
+    }
+
+    #[test]
+    fn axis_deadzone() {
+        let (gamepads, mut input) = virtual_input();
+        let id = gamepads.connect("Pad");
+        input.set_gamepad_deadzone(0.2);
+
+        gamepads.set_axis(id, GamepadAxis::LeftStickX, 0.1);
+        input.poll_gamepads();
+        assert_eq!(input.gamepad_axis(id, GamepadAxis::LeftStickX), 0.0);
+
+        gamepads.set_axis(id, GamepadAxis::LeftStickX, -0.6);
+        input.poll_gamepads();
+        assert!((input.gamepad_axis(id, GamepadAxis::LeftStickX) + 0.5).abs() < 1e-6);
+
+        gamepads.set_axis(id, GamepadAxis::LeftStickX, 1.0);
+        input.poll_gamepads();
+        assert_eq!(input.gamepad_axis(id, GamepadAxis::LeftStickX), 1.0);
+    }
+
+    #[test]
+    fn connect_and_disconnect() {
+        let (gamepads, mut input) = virtual_input();
+        let id = gamepads.connect("Pad");
+        input.poll_gamepads();
+        assert_eq!(input.connected_gamepads(), &[id]);
+        assert!(input.is_gamepad_connected(id));
+        assert_eq!(input.gamepad_name(id), Some("Pad"));
+
+        input.update();
+        assert!(input.connected_gamepads().is_empty());
+
+        gamepads.press(id, GamepadButton::South);
+        gamepads.disconnect(id);
+        input.poll_gamepads();
+        assert_eq!(input.disconnected_gamepads(), &[id]);
+        assert!(!input.is_gamepad_connected(id));
+        assert!(!input.is_gamepad_button_held(id, GamepadButton::South));
+        assert_eq!(input.gamepads().count(), 0);
+    }
+
+    #[test]
+    fn multiple_gamepads() {
+        let (gamepads, mut input) = virtual_input();
+        let first = gamepads.connect("First");
+        let second = gamepads.connect("Second");
+        assert_ne!(first, second);
+
+        gamepads.press(second, GamepadButton::North);
+        gamepads.set_axis(first, GamepadAxis::LeftStickY, 0.5);
+        gamepads.set_axis(second, GamepadAxis::LeftStickY, -1.0);
+        input.poll_gamepads();
+        assert_eq!(input.gamepads().collect::<Vec<_>>(), vec![first, second]);
+        assert!(!input.is_gamepad_button_held(first, GamepadButton::North));
+        assert!(input.is_gamepad_button_held(second, GamepadButton::North));
+
+        // Queries that aren't for one gamepad read from all of them.
+        assert!(input.was_button_pressed(InputButton::Gamepad(GamepadButton::North)));
+        assert_eq!(
+            input.axis_value(InputAxis::Gamepad(GamepadAxis::LeftStickY)),
+            -1.0
+        );
+    }
+
+    #[test]
+    fn action_map_bindings() {
+        let (gamepads, mut input) = virtual_input();
+        let first = gamepads.connect("First");
+        let second = gamepads.connect("Second");
+
+        let mut actions = ActionMap::new();
+        actions.bind(
+            "jump",
+            ActionBinding::Button(InputButton::Gamepad(GamepadButton::South)),
+        );
+        actions.bind(
+            "move",
+            ActionBinding::Axis {
+                axis: InputAxis::Gamepad(GamepadAxis::LeftStickX),
+                scale: -1.0,
+            },
+        );
+
+        gamepads.press(second, GamepadButton::South);
+        gamepads.set_axis(second, GamepadAxis::LeftStickX, 1.0);
+        input.poll_gamepads();
+        assert!(actions.was_pressed(&input, "jump"));
+        assert!(actions.is_held(&input, "jump"));
+        assert_eq!(actions.value(&input, "move"), -1.0);
+
+        actions.set_gamepad(Some(first));
+        assert!(!actions.was_pressed(&input, "jump"));
+        assert!(!actions.is_held(&input, "jump"));
+        assert_eq!(actions.value(&input, "move"), 0.0);
+
+        actions.set_gamepad(Some(second));
+        assert!(actions.was_pressed(&input, "jump"));
+        assert_eq!(actions.value(&input, "move"), -1.0);
+    }
+}
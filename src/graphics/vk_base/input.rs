@@ -1,11 +1,34 @@
-use std::{collections::HashSet, hash::Hash};
+use std::{
+    collections::{btree_map, BTreeMap, HashMap, HashSet},
+    hash::Hash,
+    time,
+};
 
-use winit::event::{ElementState, KeyboardInput, MouseButton, VirtualKeyCode, WindowEvent};
+use serde::{Deserialize, Serialize};
+use winit::event::{
+    ElementState, Ime, KeyboardInput, MouseButton, MouseScrollDelta, TouchPhase, VirtualKeyCode,
+    WindowEvent,
+};
+
+use crate::graphics::virtual_resolution::Viewport;
+
+use super::{
+    actions::{InputAxis, InputButton},
+    gamepad::*,
+    mouse::*,
+    physical_key::*,
+    recording::RecordedEvent,
+    touch::*,
+};
+
+const DEFAULT_GAMEPAD_DEADZONE: f32 = 0.15;
 
 struct ButtonSet<T: Copy + Hash + Eq> {
     pressed_buttons: HashSet<T>,
     released_buttons: HashSet<T>,
     held_buttons: HashSet<T>,
+    // Presses of buttons that were already held, ie: keyboard auto-repeat.
+    repeated_buttons: HashSet<T>,
 }
 
 impl<T: Copy + Hash + Eq> ButtonSet<T> {
@@ -14,6 +37,7 @@ impl<T: Copy + Hash + Eq> ButtonSet<T> {
             pressed_buttons: HashSet::new(),
             released_buttons: HashSet::new(),
             held_buttons: HashSet::new(),
+            repeated_buttons: HashSet::new(),
         }
     }
 
@@ -29,11 +53,17 @@ impl<T: Copy + Hash + Eq> ButtonSet<T> {
         self.held_buttons.contains(&button)
     }
 
+    pub fn was_button_pressed_or_repeated(&self, button: T) -> bool {
+        self.pressed_buttons.contains(&button) || self.repeated_buttons.contains(&button)
+    }
+
     pub fn button_state_changed(&mut self, button: T, state: ElementState) {
         match state {
             ElementState::Pressed => {
                 if self.held_buttons.insert(button) {
                     self.pressed_buttons.insert(button);
+                } else {
+                    self.repeated_buttons.insert(button);
                 }
             }
             ElementState::Released => {
@@ -46,59 +76,514 @@ impl<T: Copy + Hash + Eq> ButtonSet<T> {
     pub fn update(&mut self) {
         self.pressed_buttons.clear();
         self.released_buttons.clear();
+        self.repeated_buttons.clear();
     }
 }
 
-// TODO (involves window): Re-add mouse locking, and also add a way to close the window from user-input.
+// Everything Input reacts to, in a form that can be recorded and replayed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputEvent {
+    // Keys without a keycode are only seen by the physical key queries.
+    Key {
+        keycode: Option<VirtualKeyCode>,
+        scancode: u32,
+        state: ElementState,
+    },
+    MouseButton {
+        button: MouseButton,
+        state: ElementState,
+    },
+    CursorMoved {
+        x: f32,
+        y: f32,
+    },
+    MouseMotion {
+        delta_x: f32,
+        delta_y: f32,
+    },
+    // Scrolling by lines comes from most mouse wheels, by pixels from touchpads.
+    MouseWheel {
+        delta_x: f32,
+        delta_y: f32,
+        pixels: bool,
+    },
+    ModifiersChanged {
+        shift: bool,
+        ctrl: bool,
+        alt: bool,
+        logo: bool,
+    },
+    Character {
+        character: char,
+    },
+    // Text being composed by an input method, the cursor is a byte range into it.
+    ImePreedit {
+        text: String,
+        cursor: Option<(usize, usize)>,
+    },
+    ImeCommit {
+        text: String,
+    },
+    ImeEnabled,
+    ImeDisabled,
+    GamepadConnected {
+        id: GamepadId,
+        name: String,
+    },
+    GamepadDisconnected {
+        id: GamepadId,
+    },
+    GamepadButton {
+        id: GamepadId,
+        button: GamepadButton,
+        state: ElementState,
+    },
+    // How far an analog button like a trigger is pressed, from 0 to 1.
+    GamepadButtonValue {
+        id: GamepadId,
+        button: GamepadButton,
+        value: f32,
+    },
+    GamepadAxis {
+        id: GamepadId,
+        axis: GamepadAxis,
+        value: f32,
+    },
+    Touch {
+        id: u64,
+        phase: TouchPhase,
+        x: f32,
+        y: f32,
+        pressure: Option<f32>,
+    },
+}
+
+struct Gamepad {
+    name: String,
+    buttons: ButtonSet<GamepadButton>,
+    button_values: HashMap<GamepadButton, f32>,
+    axes: HashMap<GamepadAxis, f32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimedInputEvent {
+    pub event: InputEvent,
+    // When it happened on the input clock, see Input::time.
+    pub time: f64,
+}
+
 pub struct Input {
     keys: ButtonSet<VirtualKeyCode>,
+    physical_keys: ButtonSet<PhysicalKey>,
+    // What each physical key typed the last time it was pressed, for their labels.
+    physical_key_labels: HashMap<PhysicalKey, VirtualKeyCode>,
     mouse_buttons: ButtonSet<MouseButton>,
+    mouse_interactions: MouseInteractions,
     mouse_delta_x: f32,
     mouse_delta_y: f32,
     mouse_x: f32,
     mouse_y: f32,
+    scroll_line_delta_x: f32,
+    scroll_line_delta_y: f32,
+    scroll_pixel_delta_x: f32,
+    scroll_pixel_delta_y: f32,
+
+    shift_held: bool,
+    ctrl_held: bool,
+    alt_held: bool,
+    logo_held: bool,
+
+    // Text typed this frame, including text committed by an input method.
+    text: String,
+    ime_enabled: bool,
+    ime_preedit: String,
+    ime_preedit_cursor: Option<(usize, usize)>,
+
+    events: Vec<TimedInputEvent>,
+    // The input clock, and when the current frame began on the wall clock.
+    time: f64,
+    frame_start: time::Instant,
+    // When the event being processed happened, which clicks and touches are timed by.
+    event_time: f64,
+
+    gamepads: BTreeMap<GamepadId, Gamepad>,
+    connected_gamepads: Vec<GamepadId>,
+    disconnected_gamepads: Vec<GamepadId>,
+    gamepad_deadzone: f32,
+    gamepad_backend: Box<dyn GamepadBackend>,
+
+    touches: Touches,
+    touch_mouse_emulation: bool,
+
+    // Positions from the window are divided by the scale factor, unless using physical pixels.
+    scale_factor: f32,
+    physical_pixels: bool,
+    // Or mapped into the virtual screen while there is one.
+    virtual_viewport: Option<Viewport>,
+
+    // While replaying, events from the window are ignored.
+    live: bool,
+    recorded_events: Option<Vec<RecordedEvent>>,
 }
 
 impl Input {
     pub fn new() -> Self {
+        Self::with_gamepad_backend(Box::new(GilrsBackend::new()))
+    }
+
+    // eg: VirtualGamepads, to test gamepad controls without any plugged in.
+    pub fn with_gamepad_backend(gamepad_backend: Box<dyn GamepadBackend>) -> Self {
         Self {
             keys: ButtonSet::new(),
+            physical_keys: ButtonSet::new(),
+            physical_key_labels: HashMap::new(),
             mouse_buttons: ButtonSet::new(),
+            mouse_interactions: MouseInteractions::new(),
             mouse_delta_x: 0.0,
             mouse_delta_y: 0.0,
             mouse_x: 0.0,
             mouse_y: 0.0,
+            scroll_line_delta_x: 0.0,
+            scroll_line_delta_y: 0.0,
+            scroll_pixel_delta_x: 0.0,
+            scroll_pixel_delta_y: 0.0,
+
+            shift_held: false,
+            ctrl_held: false,
+            alt_held: false,
+            logo_held: false,
+
+            text: String::new(),
+            ime_enabled: false,
+            ime_preedit: String::new(),
+            ime_preedit_cursor: None,
+
+            events: Vec::new(),
+            time: 0.0,
+            frame_start: time::Instant::now(),
+            event_time: 0.0,
+
+            gamepads: BTreeMap::new(),
+            connected_gamepads: Vec::new(),
+            disconnected_gamepads: Vec::new(),
+            gamepad_deadzone: DEFAULT_GAMEPAD_DEADZONE,
+            gamepad_backend,
+
+            touches: Touches::new(),
+            touch_mouse_emulation: false,
+
+            scale_factor: 1.0,
+            physical_pixels: false,
+            virtual_viewport: None,
+
+            live: true,
+            recorded_events: None,
         }
     }
 
     pub fn process_button(&mut self, event: &WindowEvent) -> bool {
-        match event {
+        let (offset_x, offset_y, pixel_scale) = match self.virtual_viewport {
+            Some(viewport) => (viewport.x, viewport.y, viewport.scale),
+            None => (0.0, 0.0, self.pixel_scale()),
+        };
+
+        let event = match event {
             WindowEvent::KeyboardInput {
                 input:
                     KeyboardInput {
                         state,
-                        virtual_keycode: Some(keycode),
+                        virtual_keycode,
+                        scancode,
                         ..
                     },
                 ..
+            } => InputEvent::Key {
+                keycode: *virtual_keycode,
+                scancode: *scancode,
+                state: *state,
+            },
+            WindowEvent::MouseInput { state, button, .. } => InputEvent::MouseButton {
+                button: *button,
+                state: *state,
+            },
+            WindowEvent::CursorMoved { position, .. } => InputEvent::CursorMoved {
+                x: (position.x as f32 - offset_x) / pixel_scale,
+                y: (position.y as f32 - offset_y) / pixel_scale,
+            },
+            WindowEvent::MouseWheel { delta, .. } => match delta {
+                MouseScrollDelta::LineDelta(x, y) => InputEvent::MouseWheel {
+                    delta_x: *x,
+                    delta_y: *y,
+                    pixels: false,
+                },
+                MouseScrollDelta::PixelDelta(position) => InputEvent::MouseWheel {
+                    delta_x: position.x as f32 / pixel_scale,
+                    delta_y: position.y as f32 / pixel_scale,
+                    pixels: true,
+                },
+            },
+            WindowEvent::ModifiersChanged(modifiers) => InputEvent::ModifiersChanged {
+                shift: modifiers.shift(),
+                ctrl: modifiers.ctrl(),
+                alt: modifiers.alt(),
+                logo: modifiers.logo(),
+            },
+            WindowEvent::ReceivedCharacter(character) => InputEvent::Character {
+                character: *character,
+            },
+            WindowEvent::Ime(ime) => match ime {
+                Ime::Enabled => InputEvent::ImeEnabled,
+                Ime::Preedit(text, cursor) => InputEvent::ImePreedit {
+                    text: text.clone(),
+                    cursor: *cursor,
+                },
+                Ime::Commit(text) => InputEvent::ImeCommit { text: text.clone() },
+                Ime::Disabled => InputEvent::ImeDisabled,
+            },
+            WindowEvent::Touch(touch) => InputEvent::Touch {
+                id: touch.id,
+                phase: touch.phase,
+                x: (touch.location.x as f32 - offset_x) / pixel_scale,
+                y: (touch.location.y as f32 - offset_y) / pixel_scale,
+                pressure: touch.force.map(|force| force.normalized() as f32),
+            },
+            // Left for the window to resize itself.
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                self.scale_factor = *scale_factor as f32;
+                return false;
+            }
+            _ => return false,
+        };
+
+        self.process_live_event(event);
+
+        true
+    }
+
+    pub fn process_mouse_motion(&mut self, delta_x: f32, delta_y: f32) {
+        self.process_live_event(InputEvent::MouseMotion { delta_x, delta_y });
+    }
+
+    pub fn poll_gamepads(&mut self) {
+        while let Some(event) = self.gamepad_backend.next_event() {
+            self.process_live_event(event);
+        }
+    }
+
+    fn process_live_event(&mut self, event: InputEvent) {
+        if !self.live {
+            return;
+        }
+
+        let offset = self.frame_start.elapsed().as_secs_f32();
+        if let Some(recorded_events) = &mut self.recorded_events {
+            recorded_events.push(RecordedEvent {
+                event: event.clone(),
+                offset,
+            });
+        }
+
+        self.process_event_at(event, offset);
+    }
+
+    pub fn process_event(&mut self, event: InputEvent) {
+        self.process_event_at(event, 0.0);
+    }
+
+    // Offset is how many seconds after the previous frame began the event happened, which
+    // recordings keep so replayed events happen at the same times.
+    pub fn process_event_at(&mut self, event: InputEvent, offset: f32) {
+        self.event_time = self.time + offset as f64;
+        self.events.push(TimedInputEvent {
+            event: event.clone(),
+            time: self.event_time,
+        });
+
+        match event {
+            InputEvent::Key {
+                keycode,
+                scancode,
+                state,
             } => {
-                self.key_state_changed(*keycode, *state);
+                let physical_key = PhysicalKey::from_scancode(scancode);
+                self.physical_keys.button_state_changed(physical_key, state);
+
+                if let Some(keycode) = keycode {
+                    self.key_state_changed(keycode, state);
+                    self.physical_key_labels.insert(physical_key, keycode);
+                }
             }
-            WindowEvent::MouseInput { state, button, .. } => {
-                self.mouse_button_state_changed(*button, *state);
+            InputEvent::MouseButton { button, state } => {
+                self.mouse_button_state_changed(button, state)
             }
-            WindowEvent::CursorMoved { position, .. } => {
-                self.mouse_x = position.x as f32;
-                self.mouse_y = position.y as f32;
+            InputEvent::CursorMoved { x, y } => self.cursor_moved(x, y),
+            InputEvent::MouseMotion { delta_x, delta_y } => self.mouse_moved(delta_x, delta_y),
+            InputEvent::MouseWheel {
+                delta_x,
+                delta_y,
+                pixels,
+            } => {
+                if pixels {
+                    self.scroll_pixel_delta_x += delta_x;
+                    self.scroll_pixel_delta_y += delta_y;
+                } else {
+                    self.scroll_line_delta_x += delta_x;
+                    self.scroll_line_delta_y += delta_y;
+                }
+            }
+            InputEvent::ModifiersChanged {
+                shift,
+                ctrl,
+                alt,
+                logo,
+            } => {
+                self.shift_held = shift;
+                self.ctrl_held = ctrl;
+                self.alt_held = alt;
+                self.logo_held = logo;
+            }
+            // Control characters like backspace and enter are left to the key queries.
+            InputEvent::Character { character } => {
+                if !character.is_control() {
+                    self.text.push(character);
+                }
+            }
+            InputEvent::ImePreedit { text, cursor } => {
+                self.ime_preedit = text;
+                self.ime_preedit_cursor = cursor;
+            }
+            InputEvent::ImeCommit { text } => self.text.push_str(&text),
+            InputEvent::ImeEnabled => self.ime_enabled = true,
+            InputEvent::ImeDisabled => {
+                self.ime_enabled = false;
+                self.ime_preedit.clear();
+                self.ime_preedit_cursor = None;
+            }
+            InputEvent::GamepadConnected { id, name } => {
+                if let btree_map::Entry::Vacant(entry) = self.gamepads.entry(id) {
+                    entry.insert(Gamepad {
+                        name,
+                        buttons: ButtonSet::new(),
+                        button_values: HashMap::new(),
+                        axes: HashMap::new(),
+                    });
+                    self.connected_gamepads.push(id);
+                }
+            }
+            InputEvent::GamepadDisconnected { id } => {
+                if self.gamepads.remove(&id).is_some() {
+                    self.disconnected_gamepads.push(id);
+                }
+            }
+            InputEvent::GamepadButton { id, button, state } => {
+                if let Some(gamepad) = self.gamepads.get_mut(&id) {
+                    gamepad.buttons.button_state_changed(button, state);
+                }
+            }
+            InputEvent::GamepadButtonValue { id, button, value } => {
+                if let Some(gamepad) = self.gamepads.get_mut(&id) {
+                    gamepad.button_values.insert(button, value);
+                }
+            }
+            InputEvent::GamepadAxis { id, axis, value } => {
+                if let Some(gamepad) = self.gamepads.get_mut(&id) {
+                    gamepad.axes.insert(axis, value);
+                }
+            }
+            InputEvent::Touch {
+                id,
+                phase,
+                x,
+                y,
+                pressure,
+            } => {
+                let previous = self.touches.touch(id).map(|touch| (touch.x, touch.y));
+                self.touches
+                    .process(id, phase, x, y, pressure, self.event_time);
+
+                if self.touch_mouse_emulation && self.touches.primary() == Some(id) {
+                    self.emulate_mouse(phase, x, y, previous);
+                }
             }
-            _ => return false,
         }
+    }
 
-        true
+    // Moves the cursor with the primary touch, and holds the left mouse button while it's down.
+    fn emulate_mouse(&mut self, phase: TouchPhase, x: f32, y: f32, previous: Option<(f32, f32)>) {
+        if let Some((previous_x, previous_y)) = previous {
+            self.mouse_moved(x - previous_x, y - previous_y);
+        }
+        self.cursor_moved(x, y);
+
+        match phase {
+            TouchPhase::Started => {
+                self.mouse_button_state_changed(MouseButton::Left, ElementState::Pressed)
+            }
+            TouchPhase::Moved => {}
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.mouse_button_state_changed(MouseButton::Left, ElementState::Released)
+            }
+        }
     }
 
-    pub fn process_mouse_motion(&mut self, delta_x: f32, delta_y: f32) {
-        self.mouse_moved(delta_x, delta_y);
+    fn cursor_moved(&mut self, x: f32, y: f32) {
+        self.mouse_x = x;
+        self.mouse_y = y;
+        self.mouse_interactions.cursor_moved(x, y);
+    }
+
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        self.scale_factor = scale_factor;
+    }
+
+    pub fn set_physical_pixels(&mut self, physical_pixels: bool) {
+        self.physical_pixels = physical_pixels;
+    }
+
+    // Where the virtual screen is drawn in the window, if there is one.
+    pub fn set_virtual_viewport(&mut self, viewport: Option<Viewport>) {
+        self.virtual_viewport = viewport;
+    }
+
+    fn pixel_scale(&self) -> f32 {
+        if self.physical_pixels {
+            1.0
+        } else {
+            self.scale_factor
+        }
+    }
+
+    pub fn set_live(&mut self, live: bool) {
+        self.live = live;
+    }
+
+    // Seconds since input started, added up from each frame's delta time rather than read
+    // from the wall clock, so that replays reproduce it.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    // Called each frame after its events, before the app's update.
+    pub fn advance_time(&mut self, delta_time: f32) {
+        self.time += delta_time as f64;
+        self.frame_start = time::Instant::now();
+        self.event_time = self.time;
+    }
+
+    pub fn start_recording(&mut self) {
+        self.recorded_events = Some(Vec::new());
+    }
+
+    // Returns the live events processed since the last call, if recording.
+    pub fn take_recorded_events(&mut self) -> Option<Vec<RecordedEvent>> {
+        self.recorded_events.as_mut().map(std::mem::take)
+    }
+
+    // Every event since the last frame in the order they happened, for when the
+    // queries below lose too much, eg: a key pressed and released within one frame.
+    pub fn events(&self) -> &[TimedInputEvent] {
+        &self.events
     }
 
     pub fn was_key_pressed(&self, keycode: VirtualKeyCode) -> bool {
@@ -113,6 +598,73 @@ impl Input {
         self.keys.is_button_held(keycode)
     }
 
+    // Includes the presses sent while a key is held down, eg: to move a text cursor.
+    pub fn was_key_pressed_or_repeated(&self, keycode: VirtualKeyCode) -> bool {
+        self.keys.was_button_pressed_or_repeated(keycode)
+    }
+
+    // Physical keys stay in the same place whatever the keyboard layout, eg: for WASD movement.
+    pub fn was_physical_key_pressed(&self, key: PhysicalKey) -> bool {
+        self.physical_keys.was_button_pressed(key)
+    }
+
+    pub fn was_physical_key_released(&self, key: PhysicalKey) -> bool {
+        self.physical_keys.was_button_released(key)
+    }
+
+    pub fn is_physical_key_held(&self, key: PhysicalKey) -> bool {
+        self.physical_keys.is_button_held(key)
+    }
+
+    pub fn was_physical_key_pressed_or_repeated(&self, key: PhysicalKey) -> bool {
+        self.physical_keys.was_button_pressed_or_repeated(key)
+    }
+
+    // What the key types on the player's layout, eg: "Z" for W on French keyboards. Layouts
+    // can't be queried, so it's None until the key has been pressed. A guess can be shown
+    // until then with keycode_label on the key's us_keycode.
+    pub fn physical_key_label(&self, key: PhysicalKey) -> Option<String> {
+        self.physical_key_labels
+            .get(&key)
+            .map(|&keycode| keycode_label(keycode))
+    }
+
+    pub fn is_shift_held(&self) -> bool {
+        self.shift_held
+    }
+
+    pub fn is_ctrl_held(&self) -> bool {
+        self.ctrl_held
+    }
+
+    pub fn is_alt_held(&self) -> bool {
+        self.alt_held
+    }
+
+    // The Windows key, or Command on macOS.
+    pub fn is_logo_held(&self) -> bool {
+        self.logo_held
+    }
+
+    // Characters typed since the last frame, ready to be inserted into a text field.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_ime_enabled(&self) -> bool {
+        self.ime_enabled
+    }
+
+    // Text an input method is still composing, which should be drawn at the text
+    // cursor but not inserted until it shows up in text().
+    pub fn ime_preedit(&self) -> &str {
+        &self.ime_preedit
+    }
+
+    pub fn ime_preedit_cursor(&self) -> Option<(usize, usize)> {
+        self.ime_preedit_cursor
+    }
+
     pub fn was_mouse_button_pressed(&self, button: MouseButton) -> bool {
         self.mouse_buttons.was_button_pressed(button)
     }
@@ -125,12 +677,215 @@ impl Input {
         self.mouse_buttons.is_button_held(button)
     }
 
+    // Touches that are down, or were lifted this frame, in the order of their ids.
+    pub fn touches(&self) -> impl Iterator<Item = &Touch> {
+        self.touches.touches()
+    }
+
+    pub fn touch(&self, id: u64) -> Option<&Touch> {
+        self.touches.touch(id)
+    }
+
+    // The first finger down when no others were, eg: the one to scroll or aim with.
+    pub fn primary_touch(&self) -> Option<&Touch> {
+        self.touches.primary().and_then(|id| self.touches.touch(id))
+    }
+
+    // Gestures recognized this frame, in the order they happened.
+    pub fn gestures(&self) -> &[Gesture] {
+        self.touches.gestures()
+    }
+
+    pub fn is_touch_mouse_emulation_enabled(&self) -> bool {
+        self.touch_mouse_emulation
+    }
+
+    // Lets apps made for the mouse work on touchscreens, the primary touch acting as the left button.
+    pub fn set_touch_mouse_emulation(&mut self, enabled: bool) {
+        self.touch_mouse_emulation = enabled;
+    }
+
+    // Connected gamepads, in the order of their ids.
+    pub fn gamepads(&self) -> impl Iterator<Item = GamepadId> + '_ {
+        self.gamepads.keys().copied()
+    }
+
+    pub fn is_gamepad_connected(&self, id: GamepadId) -> bool {
+        self.gamepads.contains_key(&id)
+    }
+
+    pub fn gamepad_name(&self, id: GamepadId) -> Option<&str> {
+        self.gamepads.get(&id).map(|gamepad| &gamepad.name[..])
+    }
+
+    pub fn connected_gamepads(&self) -> &[GamepadId] {
+        &self.connected_gamepads
+    }
+
+    pub fn disconnected_gamepads(&self) -> &[GamepadId] {
+        &self.disconnected_gamepads
+    }
+
+    pub fn was_gamepad_button_pressed(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads
+            .get(&id)
+            .is_some_and(|gamepad| gamepad.buttons.was_button_pressed(button))
+    }
+
+    pub fn was_gamepad_button_released(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads
+            .get(&id)
+            .is_some_and(|gamepad| gamepad.buttons.was_button_released(button))
+    }
+
+    pub fn is_gamepad_button_held(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepads
+            .get(&id)
+            .is_some_and(|gamepad| gamepad.buttons.is_button_held(button))
+    }
+
+    pub fn gamepad_button_value(&self, id: GamepadId, button: GamepadButton) -> f32 {
+        self.gamepads
+            .get(&id)
+            .and_then(|gamepad| gamepad.button_values.get(&button).copied())
+            .unwrap_or(0.0)
+    }
+
+    // From -1 to 1, with the deadzone around the center taken out.
+    pub fn gamepad_axis(&self, id: GamepadId, axis: GamepadAxis) -> f32 {
+        let value = self
+            .gamepads
+            .get(&id)
+            .and_then(|gamepad| gamepad.axes.get(&axis).copied())
+            .unwrap_or(0.0);
+
+        if value.abs() <= self.gamepad_deadzone {
+            0.0
+        } else {
+            // Rescaled so values still start from 0 right outside the deadzone.
+            value.signum()
+                * ((value.abs() - self.gamepad_deadzone) / (1.0 - self.gamepad_deadzone)).min(1.0)
+        }
+    }
+
+    pub fn gamepad_deadzone(&self) -> f32 {
+        self.gamepad_deadzone
+    }
+
+    // Axis values closer to the center than this read as 0, since worn sticks rarely rest at it.
+    pub fn set_gamepad_deadzone(&mut self, deadzone: f32) {
+        self.gamepad_deadzone = deadzone.clamp(0.0, 0.99);
+    }
+
+    pub fn mouse_thresholds(&self) -> &MouseThresholds {
+        self.mouse_interactions.thresholds()
+    }
+
+    pub fn set_mouse_thresholds(&mut self, thresholds: MouseThresholds) {
+        self.mouse_interactions.set_thresholds(thresholds);
+    }
+
+    // Every press this frame, counting the presses before it that make it a multi click.
+    pub fn clicks(&self) -> &[Click] {
+        self.mouse_interactions.clicks()
+    }
+
+    // eg: 2 when the button was double clicked this frame, 0 if it wasn't pressed.
+    pub fn click_count(&self, button: MouseButton) -> u32 {
+        self.clicks()
+            .iter()
+            .rev()
+            .find(|click| click.button == button)
+            .map_or(0, |click| click.count)
+    }
+
+    pub fn was_double_clicked(&self, button: MouseButton) -> bool {
+        self.click_count(button) == 2
+    }
+
+    pub fn was_triple_clicked(&self, button: MouseButton) -> bool {
+        self.click_count(button) == 3
+    }
+
+    // The button's drag from the frame it moves far enough to start one, to the frame it's released.
+    pub fn mouse_drag(&self, button: MouseButton) -> Option<&MouseDrag> {
+        self.mouse_interactions.drag(button)
+    }
+
+    // Only true for the frame the button has been held long enough, without dragging.
+    pub fn was_long_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_interactions.was_long_pressed(button)
+    }
+
+    // Gamepad buttons and axes are read from every connected gamepad.
+    pub fn was_button_pressed(&self, button: InputButton) -> bool {
+        match button {
+            InputButton::Key(keycode) => self.was_key_pressed(keycode),
+            InputButton::PhysicalKey(key) => self.was_physical_key_pressed(key),
+            InputButton::Mouse(button) => self.was_mouse_button_pressed(button),
+            InputButton::Gamepad(button) => self
+                .gamepads()
+                .any(|id| self.was_gamepad_button_pressed(id, button)),
+        }
+    }
+
+    pub fn was_button_released(&self, button: InputButton) -> bool {
+        match button {
+            InputButton::Key(keycode) => self.was_key_released(keycode),
+            InputButton::PhysicalKey(key) => self.was_physical_key_released(key),
+            InputButton::Mouse(button) => self.was_mouse_button_released(button),
+            InputButton::Gamepad(button) => self
+                .gamepads()
+                .any(|id| self.was_gamepad_button_released(id, button)),
+        }
+    }
+
+    pub fn is_button_held(&self, button: InputButton) -> bool {
+        match button {
+            InputButton::Key(keycode) => self.is_key_held(keycode),
+            InputButton::PhysicalKey(key) => self.is_physical_key_held(key),
+            InputButton::Mouse(button) => self.is_mouse_button_held(button),
+            InputButton::Gamepad(button) => self
+                .gamepads()
+                .any(|id| self.is_gamepad_button_held(id, button)),
+        }
+    }
+
+    pub fn axis_value(&self, axis: InputAxis) -> f32 {
+        match axis {
+            InputAxis::MouseDeltaX => self.mouse_delta_x,
+            InputAxis::MouseDeltaY => self.mouse_delta_y,
+            InputAxis::ScrollLinesX => self.scroll_line_delta_x,
+            InputAxis::ScrollLinesY => self.scroll_line_delta_y,
+            InputAxis::ScrollPixelsX => self.scroll_pixel_delta_x,
+            InputAxis::ScrollPixelsY => self.scroll_pixel_delta_y,
+            // Whichever gamepad's axis is furthest from the center.
+            InputAxis::Gamepad(axis) => self.gamepads().map(|id| self.gamepad_axis(id, axis)).fold(
+                0.0,
+                |value: f32, gamepad_value: f32| {
+                    if gamepad_value.abs() > value.abs() {
+                        gamepad_value
+                    } else {
+                        value
+                    }
+                },
+            ),
+        }
+    }
+
     pub fn key_state_changed(&mut self, keycode: VirtualKeyCode, state: ElementState) {
         self.keys.button_state_changed(keycode, state);
     }
 
     pub fn mouse_button_state_changed(&mut self, button: MouseButton, state: ElementState) {
         self.mouse_buttons.button_state_changed(button, state);
+        self.mouse_interactions.button_state_changed(
+            button,
+            state,
+            self.mouse_x,
+            self.mouse_y,
+            self.event_time,
+        );
     }
 
     pub fn mouse_moved(&mut self, delta_x: f32, delta_y: f32) {
@@ -154,10 +909,40 @@ impl Input {
         self.mouse_delta_y
     }
 
+    pub fn scroll_line_delta_x(&self) -> f32 {
+        self.scroll_line_delta_x
+    }
+
+    pub fn scroll_line_delta_y(&self) -> f32 {
+        self.scroll_line_delta_y
+    }
+
+    pub fn scroll_pixel_delta_x(&self) -> f32 {
+        self.scroll_pixel_delta_x
+    }
+
+    pub fn scroll_pixel_delta_y(&self) -> f32 {
+        self.scroll_pixel_delta_y
+    }
+
     pub fn update(&mut self) {
         self.keys.update();
+        self.physical_keys.update();
         self.mouse_buttons.update();
+        self.mouse_interactions.update(self.time);
         self.mouse_delta_x = 0.0;
         self.mouse_delta_y = 0.0;
+        self.scroll_line_delta_x = 0.0;
+        self.scroll_line_delta_y = 0.0;
+        self.scroll_pixel_delta_x = 0.0;
+        self.scroll_pixel_delta_y = 0.0;
+        self.text.clear();
+        self.events.clear();
+        self.touches.update();
+        self.connected_gamepads.clear();
+        self.disconnected_gamepads.clear();
+        for gamepad in self.gamepads.values_mut() {
+            gamepad.buttons.update();
+        }
     }
 }
@@ -1,10 +1,16 @@
+pub mod actions;
 pub mod command_data;
 pub mod device_data;
+pub mod gamepad;
 pub mod input;
 pub mod instance_data;
+pub mod mouse;
+pub mod physical_key;
+pub mod recording;
 pub mod surface_data;
 pub mod swapchain_data;
 pub mod sync_data;
+pub mod touch;
 
 use ash::extensions::ext;
 use ash::vk;
@@ -85,13 +91,13 @@ pub struct VkBase {
 }
 
 impl VkBase {
-    pub fn render_loop<F: FnMut(&mut input::Input)>(
+    // Runs until the window is closed or the frame callback returns false.
+    pub fn render_loop<F: FnMut(&mut input::Input) -> bool>(
         window: &winit::window::Window,
         event_loop: &mut EventLoop<()>,
+        mut input: input::Input,
         mut f: F,
     ) {
-        let mut input = input::Input::new();
-
         event_loop.run_return(|event, _, control_flow| match event {
             Event::WindowEvent { ref event, .. } if input.process_button(event) => {}
             Event::WindowEvent {
@@ -112,7 +118,11 @@ impl VkBase {
 
                 control_flow.set_poll();
 
-                f(&mut input);
+                input.poll_gamepads();
+
+                if !f(&mut input) {
+                    control_flow.set_exit();
+                }
                 input.update();
             }
             Event::DeviceEvent {
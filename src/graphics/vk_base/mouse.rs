@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use winit::event::{ElementState, MouseButton};
+
+use crate::graphics::vec2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MouseThresholds {
+    // Longest time, and furthest distance, between presses for them to count as one multi click.
+    pub multi_click_time: f32,
+    pub multi_click_distance: f32,
+    // How far the cursor moves while a button is held before it's a drag.
+    pub drag_distance: f32,
+    // How long a button is held, without dragging, before it's a long press.
+    pub long_press_time: f32,
+}
+
+impl Default for MouseThresholds {
+    fn default() -> Self {
+        Self {
+            multi_click_time: 0.4,
+            multi_click_distance: 4.0,
+            drag_distance: 4.0,
+            long_press_time: 0.5,
+        }
+    }
+}
+
+// Sent when the button is pressed, like most desktops do, count being 2 for double clicks.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Click {
+    pub button: MouseButton,
+    pub count: u32,
+    pub x: f32,
+    pub y: f32,
+}
+
+// Started and Ended drags are only seen the frame it happened, Moved after that.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DragPhase {
+    Started,
+    Moved,
+    Ended,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MouseDrag {
+    pub button: MouseButton,
+    pub phase: DragPhase,
+    // Where the button was pressed, rather than where the drag threshold was passed.
+    pub start_x: f32,
+    pub start_y: f32,
+    pub x: f32,
+    pub y: f32,
+    // How far the cursor moved since the drag started.
+    pub delta_x: f32,
+    pub delta_y: f32,
+}
+
+struct Press {
+    x: f32,
+    y: f32,
+    // On the input clock, see Input::time.
+    time: f64,
+    dragging: bool,
+    long_pressed: bool,
+}
+
+// Clicks, drags and long presses, derived from the mouse buttons and cursor.
+pub(super) struct MouseInteractions {
+    thresholds: MouseThresholds,
+    presses: HashMap<MouseButton, Press>,
+    last_click: Option<(Click, f64)>,
+    clicks: Vec<Click>,
+    drags: HashMap<MouseButton, MouseDrag>,
+    long_presses: Vec<MouseButton>,
+}
+
+impl MouseInteractions {
+    pub fn new() -> Self {
+        Self {
+            thresholds: MouseThresholds::default(),
+            presses: HashMap::new(),
+            last_click: None,
+            clicks: Vec::new(),
+            drags: HashMap::new(),
+            long_presses: Vec::new(),
+        }
+    }
+
+    pub fn thresholds(&self) -> &MouseThresholds {
+        &self.thresholds
+    }
+
+    pub fn set_thresholds(&mut self, thresholds: MouseThresholds) {
+        self.thresholds = thresholds;
+    }
+
+    pub fn clicks(&self) -> &[Click] {
+        &self.clicks
+    }
+
+    pub fn drag(&self, button: MouseButton) -> Option<&MouseDrag> {
+        self.drags.get(&button)
+    }
+
+    pub fn was_long_pressed(&self, button: MouseButton) -> bool {
+        self.long_presses.contains(&button)
+    }
+
+    pub fn button_state_changed(
+        &mut self,
+        button: MouseButton,
+        state: ElementState,
+        x: f32,
+        y: f32,
+        time: f64,
+    ) {
+        match state {
+            ElementState::Pressed => self.press(button, x, y, time),
+            ElementState::Released => self.release(button, x, y),
+        }
+    }
+
+    fn press(&mut self, button: MouseButton, x: f32, y: f32, time: f64) {
+        let count = match self.last_click {
+            Some((last_click, last_time))
+                if last_click.button == button
+                    && (time - last_time) as f32 <= self.thresholds.multi_click_time
+                    && vec2::distance([last_click.x, last_click.y], [x, y])
+                        <= self.thresholds.multi_click_distance =>
+            {
+                last_click.count + 1
+            }
+            _ => 1,
+        };
+
+        let click = Click {
+            button,
+            count,
+            x,
+            y,
+        };
+        self.clicks.push(click);
+        self.last_click = Some((click, time));
+
+        self.presses.insert(
+            button,
+            Press {
+                x,
+                y,
+                time,
+                dragging: false,
+                long_pressed: false,
+            },
+        );
+    }
+
+    fn release(&mut self, button: MouseButton, x: f32, y: f32) {
+        self.cursor_moved(x, y);
+
+        if self
+            .presses
+            .remove(&button)
+            .is_some_and(|press| press.dragging)
+        {
+            if let Some(drag) = self.drags.get_mut(&button) {
+                drag.phase = DragPhase::Ended;
+            }
+        }
+    }
+
+    pub fn cursor_moved(&mut self, x: f32, y: f32) {
+        for (&button, press) in self.presses.iter_mut() {
+            if !press.dragging
+                && vec2::distance([press.x, press.y], [x, y]) > self.thresholds.drag_distance
+            {
+                press.dragging = true;
+                self.drags.insert(
+                    button,
+                    MouseDrag {
+                        button,
+                        phase: DragPhase::Started,
+                        start_x: press.x,
+                        start_y: press.y,
+                        x,
+                        y,
+                        delta_x: 0.0,
+                        delta_y: 0.0,
+                    },
+                );
+            }
+
+            if let Some(drag) = self.drags.get_mut(&button) {
+                drag.x = x;
+                drag.y = y;
+                drag.delta_x = x - drag.start_x;
+                drag.delta_y = y - drag.start_y;
+            }
+        }
+    }
+
+    // Long presses found here are seen during the next frame.
+    pub fn update(&mut self, time: f64) {
+        self.clicks.clear();
+        self.long_presses.clear();
+
+        self.drags.retain(|_, drag| drag.phase != DragPhase::Ended);
+        for drag in self.drags.values_mut() {
+            drag.phase = DragPhase::Moved;
+        }
+
+        for (&button, press) in self.presses.iter_mut() {
+            if !press.dragging
+                && !press.long_pressed
+                && (time - press.time) as f32 >= self.thresholds.long_press_time
+            {
+                press.long_pressed = true;
+                self.long_presses.push(button);
+            }
+        }
+    }
+}
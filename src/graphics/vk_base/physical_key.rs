@@ -0,0 +1,363 @@
+use serde::{Deserialize, Serialize};
+use winit::event::VirtualKeyCode;
+
+// A key by where it is on the keyboard rather than what it types, named after the
+// key in that spot on a US layout. eg: W is the key typing Z on French keyboards.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PhysicalKey {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    Key1,
+    Key2,
+    Key3,
+    Key4,
+    Key5,
+    Key6,
+    Key7,
+    Key8,
+    Key9,
+    Key0,
+    Minus,
+    Equals,
+    LBracket,
+    RBracket,
+    Backslash,
+    Semicolon,
+    Apostrophe,
+    Grave,
+    Comma,
+    Period,
+    Slash,
+    Escape,
+    Tab,
+    Back,
+    Return,
+    Space,
+    CapsLock,
+    LShift,
+    RShift,
+    LControl,
+    RControl,
+    LAlt,
+    RAlt,
+    Up,
+    Down,
+    Left,
+    Right,
+    // Any other key, by its platform specific scancode.
+    Other(u32),
+}
+
+// What each key types on a US layout.
+const US_KEYCODES: &[(PhysicalKey, VirtualKeyCode)] = &[
+    (PhysicalKey::A, VirtualKeyCode::A),
+    (PhysicalKey::B, VirtualKeyCode::B),
+    (PhysicalKey::C, VirtualKeyCode::C),
+    (PhysicalKey::D, VirtualKeyCode::D),
+    (PhysicalKey::E, VirtualKeyCode::E),
+    (PhysicalKey::F, VirtualKeyCode::F),
+    (PhysicalKey::G, VirtualKeyCode::G),
+    (PhysicalKey::H, VirtualKeyCode::H),
+    (PhysicalKey::I, VirtualKeyCode::I),
+    (PhysicalKey::J, VirtualKeyCode::J),
+    (PhysicalKey::K, VirtualKeyCode::K),
+    (PhysicalKey::L, VirtualKeyCode::L),
+    (PhysicalKey::M, VirtualKeyCode::M),
+    (PhysicalKey::N, VirtualKeyCode::N),
+    (PhysicalKey::O, VirtualKeyCode::O),
+    (PhysicalKey::P, VirtualKeyCode::P),
+    (PhysicalKey::Q, VirtualKeyCode::Q),
+    (PhysicalKey::R, VirtualKeyCode::R),
+    (PhysicalKey::S, VirtualKeyCode::S),
+    (PhysicalKey::T, VirtualKeyCode::T),
+    (PhysicalKey::U, VirtualKeyCode::U),
+    (PhysicalKey::V, VirtualKeyCode::V),
+    (PhysicalKey::W, VirtualKeyCode::W),
+    (PhysicalKey::X, VirtualKeyCode::X),
+    (PhysicalKey::Y, VirtualKeyCode::Y),
+    (PhysicalKey::Z, VirtualKeyCode::Z),
+    (PhysicalKey::Key1, VirtualKeyCode::Key1),
+    (PhysicalKey::Key2, VirtualKeyCode::Key2),
+    (PhysicalKey::Key3, VirtualKeyCode::Key3),
+    (PhysicalKey::Key4, VirtualKeyCode::Key4),
+    (PhysicalKey::Key5, VirtualKeyCode::Key5),
+    (PhysicalKey::Key6, VirtualKeyCode::Key6),
+    (PhysicalKey::Key7, VirtualKeyCode::Key7),
+    (PhysicalKey::Key8, VirtualKeyCode::Key8),
+    (PhysicalKey::Key9, VirtualKeyCode::Key9),
+    (PhysicalKey::Key0, VirtualKeyCode::Key0),
+    (PhysicalKey::Minus, VirtualKeyCode::Minus),
+    (PhysicalKey::Equals, VirtualKeyCode::Equals),
+    (PhysicalKey::LBracket, VirtualKeyCode::LBracket),
+    (PhysicalKey::RBracket, VirtualKeyCode::RBracket),
+    (PhysicalKey::Backslash, VirtualKeyCode::Backslash),
+    (PhysicalKey::Semicolon, VirtualKeyCode::Semicolon),
+    (PhysicalKey::Apostrophe, VirtualKeyCode::Apostrophe),
+    (PhysicalKey::Grave, VirtualKeyCode::Grave),
+    (PhysicalKey::Comma, VirtualKeyCode::Comma),
+    (PhysicalKey::Period, VirtualKeyCode::Period),
+    (PhysicalKey::Slash, VirtualKeyCode::Slash),
+    (PhysicalKey::Escape, VirtualKeyCode::Escape),
+    (PhysicalKey::Tab, VirtualKeyCode::Tab),
+    (PhysicalKey::Back, VirtualKeyCode::Back),
+    (PhysicalKey::Return, VirtualKeyCode::Return),
+    (PhysicalKey::Space, VirtualKeyCode::Space),
+    (PhysicalKey::CapsLock, VirtualKeyCode::Capital),
+    (PhysicalKey::LShift, VirtualKeyCode::LShift),
+    (PhysicalKey::RShift, VirtualKeyCode::RShift),
+    (PhysicalKey::LControl, VirtualKeyCode::LControl),
+    (PhysicalKey::RControl, VirtualKeyCode::RControl),
+    (PhysicalKey::LAlt, VirtualKeyCode::LAlt),
+    (PhysicalKey::RAlt, VirtualKeyCode::RAlt),
+    (PhysicalKey::Up, VirtualKeyCode::Up),
+    (PhysicalKey::Down, VirtualKeyCode::Down),
+    (PhysicalKey::Left, VirtualKeyCode::Left),
+    (PhysicalKey::Right, VirtualKeyCode::Right),
+];
+
+// Windows and Linux scancodes both come from PC keyboards, so they only differ for
+// extended keys, which Windows marks with 0xE000.
+#[cfg(not(target_os = "macos"))]
+const SCANCODES: &[(PhysicalKey, u32)] = &[
+    (PhysicalKey::Escape, 0x01),
+    (PhysicalKey::Key1, 0x02),
+    (PhysicalKey::Key2, 0x03),
+    (PhysicalKey::Key3, 0x04),
+    (PhysicalKey::Key4, 0x05),
+    (PhysicalKey::Key5, 0x06),
+    (PhysicalKey::Key6, 0x07),
+    (PhysicalKey::Key7, 0x08),
+    (PhysicalKey::Key8, 0x09),
+    (PhysicalKey::Key9, 0x0A),
+    (PhysicalKey::Key0, 0x0B),
+    (PhysicalKey::Minus, 0x0C),
+    (PhysicalKey::Equals, 0x0D),
+    (PhysicalKey::Back, 0x0E),
+    (PhysicalKey::Tab, 0x0F),
+    (PhysicalKey::Q, 0x10),
+    (PhysicalKey::W, 0x11),
+    (PhysicalKey::E, 0x12),
+    (PhysicalKey::R, 0x13),
+    (PhysicalKey::T, 0x14),
+    (PhysicalKey::Y, 0x15),
+    (PhysicalKey::U, 0x16),
+    (PhysicalKey::I, 0x17),
+    (PhysicalKey::O, 0x18),
+    (PhysicalKey::P, 0x19),
+    (PhysicalKey::LBracket, 0x1A),
+    (PhysicalKey::RBracket, 0x1B),
+    (PhysicalKey::Return, 0x1C),
+    (PhysicalKey::LControl, 0x1D),
+    (PhysicalKey::A, 0x1E),
+    (PhysicalKey::S, 0x1F),
+    (PhysicalKey::D, 0x20),
+    (PhysicalKey::F, 0x21),
+    (PhysicalKey::G, 0x22),
+    (PhysicalKey::H, 0x23),
+    (PhysicalKey::J, 0x24),
+    (PhysicalKey::K, 0x25),
+    (PhysicalKey::L, 0x26),
+    (PhysicalKey::Semicolon, 0x27),
+    (PhysicalKey::Apostrophe, 0x28),
+    (PhysicalKey::Grave, 0x29),
+    (PhysicalKey::LShift, 0x2A),
+    (PhysicalKey::Backslash, 0x2B),
+    (PhysicalKey::Z, 0x2C),
+    (PhysicalKey::X, 0x2D),
+    (PhysicalKey::C, 0x2E),
+    (PhysicalKey::V, 0x2F),
+    (PhysicalKey::B, 0x30),
+    (PhysicalKey::N, 0x31),
+    (PhysicalKey::M, 0x32),
+    (PhysicalKey::Comma, 0x33),
+    (PhysicalKey::Period, 0x34),
+    (PhysicalKey::Slash, 0x35),
+    (PhysicalKey::RShift, 0x36),
+    (PhysicalKey::LAlt, 0x38),
+    (PhysicalKey::Space, 0x39),
+    (PhysicalKey::CapsLock, 0x3A),
+    #[cfg(target_os = "windows")]
+    (PhysicalKey::RControl, 0xE01D),
+    #[cfg(target_os = "windows")]
+    (PhysicalKey::RAlt, 0xE038),
+    #[cfg(target_os = "windows")]
+    (PhysicalKey::Up, 0xE048),
+    #[cfg(target_os = "windows")]
+    (PhysicalKey::Left, 0xE04B),
+    #[cfg(target_os = "windows")]
+    (PhysicalKey::Right, 0xE04D),
+    #[cfg(target_os = "windows")]
+    (PhysicalKey::Down, 0xE050),
+    #[cfg(not(target_os = "windows"))]
+    (PhysicalKey::RControl, 97),
+    #[cfg(not(target_os = "windows"))]
+    (PhysicalKey::RAlt, 100),
+    #[cfg(not(target_os = "windows"))]
+    (PhysicalKey::Up, 103),
+    #[cfg(not(target_os = "windows"))]
+    (PhysicalKey::Left, 105),
+    #[cfg(not(target_os = "windows"))]
+    (PhysicalKey::Right, 106),
+    #[cfg(not(target_os = "windows"))]
+    (PhysicalKey::Down, 108),
+];
+
+#[cfg(target_os = "macos")]
+const SCANCODES: &[(PhysicalKey, u32)] = &[
+    (PhysicalKey::A, 0x00),
+    (PhysicalKey::S, 0x01),
+    (PhysicalKey::D, 0x02),
+    (PhysicalKey::F, 0x03),
+    (PhysicalKey::H, 0x04),
+    (PhysicalKey::G, 0x05),
+    (PhysicalKey::Z, 0x06),
+    (PhysicalKey::X, 0x07),
+    (PhysicalKey::C, 0x08),
+    (PhysicalKey::V, 0x09),
+    (PhysicalKey::B, 0x0B),
+    (PhysicalKey::Q, 0x0C),
+    (PhysicalKey::W, 0x0D),
+    (PhysicalKey::E, 0x0E),
+    (PhysicalKey::R, 0x0F),
+    (PhysicalKey::Y, 0x10),
+    (PhysicalKey::T, 0x11),
+    (PhysicalKey::Key1, 0x12),
+    (PhysicalKey::Key2, 0x13),
+    (PhysicalKey::Key3, 0x14),
+    (PhysicalKey::Key4, 0x15),
+    (PhysicalKey::Key6, 0x16),
+    (PhysicalKey::Key5, 0x17),
+    (PhysicalKey::Equals, 0x18),
+    (PhysicalKey::Key9, 0x19),
+    (PhysicalKey::Key7, 0x1A),
+    (PhysicalKey::Minus, 0x1B),
+    (PhysicalKey::Key8, 0x1C),
+    (PhysicalKey::Key0, 0x1D),
+    (PhysicalKey::RBracket, 0x1E),
+    (PhysicalKey::O, 0x1F),
+    (PhysicalKey::U, 0x20),
+    (PhysicalKey::LBracket, 0x21),
+    (PhysicalKey::I, 0x22),
+    (PhysicalKey::P, 0x23),
+    (PhysicalKey::Return, 0x24),
+    (PhysicalKey::L, 0x25),
+    (PhysicalKey::J, 0x26),
+    (PhysicalKey::Apostrophe, 0x27),
+    (PhysicalKey::K, 0x28),
+    (PhysicalKey::Semicolon, 0x29),
+    (PhysicalKey::Backslash, 0x2A),
+    (PhysicalKey::Comma, 0x2B),
+    (PhysicalKey::Slash, 0x2C),
+    (PhysicalKey::N, 0x2D),
+    (PhysicalKey::M, 0x2E),
+    (PhysicalKey::Period, 0x2F),
+    (PhysicalKey::Tab, 0x30),
+    (PhysicalKey::Space, 0x31),
+    (PhysicalKey::Grave, 0x32),
+    (PhysicalKey::Back, 0x33),
+    (PhysicalKey::Escape, 0x35),
+    (PhysicalKey::LShift, 0x38),
+    (PhysicalKey::CapsLock, 0x39),
+    (PhysicalKey::LAlt, 0x3A),
+    (PhysicalKey::LControl, 0x3B),
+    (PhysicalKey::RShift, 0x3C),
+    (PhysicalKey::RAlt, 0x3D),
+    (PhysicalKey::RControl, 0x3E),
+    (PhysicalKey::Left, 0x7B),
+    (PhysicalKey::Right, 0x7C),
+    (PhysicalKey::Down, 0x7D),
+    (PhysicalKey::Up, 0x7E),
+];
+
+impl PhysicalKey {
+    pub fn from_scancode(scancode: u32) -> Self {
+        SCANCODES
+            .iter()
+            .find(|&&(_, other)| other == scancode)
+            .map(|&(key, _)| key)
+            .unwrap_or(PhysicalKey::Other(scancode))
+    }
+
+    pub fn scancode(self) -> u32 {
+        match self {
+            PhysicalKey::Other(scancode) => scancode,
+            _ => SCANCODES
+                .iter()
+                .find(|&&(key, _)| key == self)
+                .map(|&(_, scancode)| scancode)
+                .unwrap(),
+        }
+    }
+
+    // What the key types on a US layout, None for Other keys.
+    pub fn us_keycode(self) -> Option<VirtualKeyCode> {
+        US_KEYCODES
+            .iter()
+            .find(|&&(key, _)| key == self)
+            .map(|&(_, keycode)| keycode)
+    }
+}
+
+// A short name for a key to show to players, eg: "1" rather than "Key1".
+pub fn keycode_label(keycode: VirtualKeyCode) -> String {
+    let label = match keycode {
+        VirtualKeyCode::Key1 => "1",
+        VirtualKeyCode::Key2 => "2",
+        VirtualKeyCode::Key3 => "3",
+        VirtualKeyCode::Key4 => "4",
+        VirtualKeyCode::Key5 => "5",
+        VirtualKeyCode::Key6 => "6",
+        VirtualKeyCode::Key7 => "7",
+        VirtualKeyCode::Key8 => "8",
+        VirtualKeyCode::Key9 => "9",
+        VirtualKeyCode::Key0 => "0",
+        VirtualKeyCode::Minus => "-",
+        VirtualKeyCode::Equals => "=",
+        VirtualKeyCode::LBracket => "[",
+        VirtualKeyCode::RBracket => "]",
+        VirtualKeyCode::Backslash => "\\",
+        VirtualKeyCode::Semicolon => ";",
+        VirtualKeyCode::Apostrophe => "'",
+        VirtualKeyCode::Grave => "`",
+        VirtualKeyCode::Comma => ",",
+        VirtualKeyCode::Period => ".",
+        VirtualKeyCode::Slash => "/",
+        VirtualKeyCode::Back => "Backspace",
+        VirtualKeyCode::Return => "Enter",
+        VirtualKeyCode::Capital => "Caps Lock",
+        VirtualKeyCode::LShift => "Left Shift",
+        VirtualKeyCode::RShift => "Right Shift",
+        VirtualKeyCode::LControl => "Left Ctrl",
+        VirtualKeyCode::RControl => "Right Ctrl",
+        VirtualKeyCode::LAlt => "Left Alt",
+        VirtualKeyCode::RAlt => "Right Alt",
+        _ => return format!("{:?}", keycode),
+    };
+
+    label.to_string()
+}
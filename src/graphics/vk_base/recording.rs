@@ -0,0 +1,75 @@
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::input::InputEvent;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub event: InputEvent,
+    // Seconds after the previous frame began, see Input::process_event_at.
+    pub offset: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    pub delta_time: f32,
+    // Events processed before the frame's update.
+    pub events: Vec<RecordedEvent>,
+}
+
+// Writes each frame as a line of JSON as soon as it's recorded, so that
+// recordings of crashes aren't lost.
+pub struct Recorder {
+    writer: io::BufWriter<fs::File>,
+}
+
+impl Recorder {
+    pub fn new(path: &str) -> Self {
+        let file =
+            fs::File::create(path).unwrap_or_else(|_| panic!("Failed to create file {}", path));
+
+        Self {
+            writer: io::BufWriter::new(file),
+        }
+    }
+
+    pub fn record(&mut self, frame: &RecordedFrame) {
+        serde_json::to_writer(&mut self.writer, frame).expect("Failed to write recorded frame");
+        writeln!(self.writer).expect("Failed to write recorded frame");
+        self.writer.flush().expect("Failed to write recorded frame");
+    }
+}
+
+pub struct Replay {
+    frames: Vec<RecordedFrame>,
+    next_frame: usize,
+}
+
+impl Replay {
+    pub fn load(path: &str) -> Self {
+        let file = fs::File::open(path).unwrap_or_else(|_| panic!("Failed to load file {}", path));
+
+        let frames = io::BufReader::new(file)
+            .lines()
+            .map(|line| line.unwrap_or_else(|_| panic!("Failed to load file {}", path)))
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(&line).expect("Failed to parse recorded frame"))
+            .collect();
+
+        Self {
+            frames,
+            next_frame: 0,
+        }
+    }
+
+    pub fn next_frame(&mut self) -> Option<&RecordedFrame> {
+        let frame = self.frames.get(self.next_frame)?;
+        self.next_frame += 1;
+
+        Some(frame)
+    }
+}
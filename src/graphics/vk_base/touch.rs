@@ -0,0 +1,271 @@
+use std::collections::BTreeMap;
+
+pub use winit::event::TouchPhase;
+
+use crate::graphics::vec2;
+
+// Longest a touch can be held, and furthest it can move, to still count as a tap.
+const TAP_MAX_DURATION: f32 = 0.3;
+const TAP_MAX_DISTANCE: f32 = 10.0;
+// Longest time, and furthest distance, between two taps for them to be a double tap.
+const DOUBLE_TAP_MAX_INTERVAL: f32 = 0.3;
+const DOUBLE_TAP_MAX_DISTANCE: f32 = 30.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Touch {
+    pub id: u64,
+    // Started and Ended touches are only seen the frame it happened, Moved after that.
+    pub phase: TouchPhase,
+    pub x: f32,
+    pub y: f32,
+    pub start_x: f32,
+    pub start_y: f32,
+    // How far the touch moved this frame.
+    pub delta_x: f32,
+    pub delta_y: f32,
+    // From 0 to 1, if the touchscreen reports it.
+    pub pressure: Option<f32>,
+
+    // On the input clock, see Input::time.
+    start_time: f64,
+    // Set once it moves too far or another finger touches, it's then no longer a tap.
+    dragged: bool,
+    multi_touch: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gesture {
+    // The first tap of a double tap is also sent as a tap.
+    Tap {
+        x: f32,
+        y: f32,
+    },
+    DoubleTap {
+        x: f32,
+        y: f32,
+    },
+    // A single touch moving, sent each time it moves.
+    Drag {
+        x: f32,
+        y: f32,
+        delta_x: f32,
+        delta_y: f32,
+    },
+    // Two touches moving apart or together, scale being how much their distance changed.
+    Pinch {
+        x: f32,
+        y: f32,
+        scale: f32,
+    },
+    // Two touches moving in the same direction, by how far their center moved.
+    Pan {
+        delta_x: f32,
+        delta_y: f32,
+    },
+}
+
+// Touches currently down, and the gestures they made this frame.
+pub(super) struct Touches {
+    touches: BTreeMap<u64, Touch>,
+    // The touch that started while no others were down, which can emulate the mouse.
+    primary: Option<u64>,
+    gestures: Vec<Gesture>,
+    last_tap: Option<(f64, f32, f32)>,
+}
+
+impl Touches {
+    pub fn new() -> Self {
+        Self {
+            touches: BTreeMap::new(),
+            primary: None,
+            gestures: Vec::new(),
+            last_tap: None,
+        }
+    }
+
+    pub fn touches(&self) -> impl Iterator<Item = &Touch> {
+        self.touches.values()
+    }
+
+    pub fn touch(&self, id: u64) -> Option<&Touch> {
+        self.touches.get(&id)
+    }
+
+    pub fn primary(&self) -> Option<u64> {
+        self.primary
+    }
+
+    pub fn gestures(&self) -> &[Gesture] {
+        &self.gestures
+    }
+
+    pub fn process(
+        &mut self,
+        id: u64,
+        phase: TouchPhase,
+        x: f32,
+        y: f32,
+        pressure: Option<f32>,
+        time: f64,
+    ) {
+        match phase {
+            TouchPhase::Started => self.start(id, x, y, pressure, time),
+            TouchPhase::Moved => self.move_touch(id, x, y, pressure),
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.move_touch(id, x, y, pressure);
+                self.end(id, phase, time);
+            }
+        }
+    }
+
+    fn start(&mut self, id: u64, x: f32, y: f32, pressure: Option<f32>, time: f64) {
+        if self.touches.is_empty() {
+            self.primary = Some(id);
+        }
+
+        let multi_touch = !self.touches.is_empty();
+        for touch in self.touches.values_mut() {
+            touch.multi_touch = true;
+        }
+
+        self.touches.insert(
+            id,
+            Touch {
+                id,
+                phase: TouchPhase::Started,
+                x,
+                y,
+                start_x: x,
+                start_y: y,
+                delta_x: 0.0,
+                delta_y: 0.0,
+                pressure,
+
+                start_time: time,
+                dragged: false,
+                multi_touch,
+            },
+        );
+    }
+
+    fn move_touch(&mut self, id: u64, x: f32, y: f32, pressure: Option<f32>) {
+        let pair = self.pair_center_and_distance();
+        let single = self.touches.len() == 1;
+
+        let Some(touch) = self.touches.get_mut(&id) else {
+            return;
+        };
+
+        touch.pressure = pressure;
+
+        let delta_x = x - touch.x;
+        let delta_y = y - touch.y;
+        if delta_x == 0.0 && delta_y == 0.0 {
+            return;
+        }
+
+        touch.x = x;
+        touch.y = y;
+        touch.delta_x += delta_x;
+        touch.delta_y += delta_y;
+        if touch.phase != TouchPhase::Started {
+            touch.phase = TouchPhase::Moved;
+        }
+
+        if vec2::distance([touch.start_x, touch.start_y], [x, y]) > TAP_MAX_DISTANCE {
+            touch.dragged = true;
+        }
+
+        if single && touch.dragged {
+            self.gestures.push(Gesture::Drag {
+                x,
+                y,
+                delta_x,
+                delta_y,
+            });
+        }
+
+        if let (Some((old_x, old_y, old_distance)), Some((new_x, new_y, new_distance))) =
+            (pair, self.pair_center_and_distance())
+        {
+            if old_distance > 0.0 && new_distance != old_distance {
+                self.gestures.push(Gesture::Pinch {
+                    x: new_x,
+                    y: new_y,
+                    scale: new_distance / old_distance,
+                });
+            }
+            if new_x != old_x || new_y != old_y {
+                self.gestures.push(Gesture::Pan {
+                    delta_x: new_x - old_x,
+                    delta_y: new_y - old_y,
+                });
+            }
+        }
+    }
+
+    fn end(&mut self, id: u64, phase: TouchPhase, time: f64) {
+        let Some(touch) = self.touches.get_mut(&id) else {
+            return;
+        };
+        touch.phase = phase;
+
+        let is_tap = phase == TouchPhase::Ended
+            && !touch.dragged
+            && !touch.multi_touch
+            && (time - touch.start_time) as f32 <= TAP_MAX_DURATION;
+        if !is_tap {
+            return;
+        }
+
+        let (x, y) = (touch.x, touch.y);
+        self.gestures.push(Gesture::Tap { x, y });
+
+        let is_double_tap = self.last_tap.is_some_and(|(last_time, last_x, last_y)| {
+            (time - last_time) as f32 <= DOUBLE_TAP_MAX_INTERVAL
+                && vec2::distance([last_x, last_y], [x, y]) <= DOUBLE_TAP_MAX_DISTANCE
+        });
+        if is_double_tap {
+            self.gestures.push(Gesture::DoubleTap { x, y });
+            // A third tap starts a new double tap rather than finishing another one.
+            self.last_tap = None;
+        } else {
+            self.last_tap = Some((time, x, y));
+        }
+    }
+
+    // The center of, and distance between, the touches while exactly two are down.
+    fn pair_center_and_distance(&self) -> Option<(f32, f32, f32)> {
+        if self.touches.len() != 2 {
+            return None;
+        }
+
+        let mut touches = self.touches.values();
+        let (a, b) = (touches.next()?, touches.next()?);
+
+        Some((
+            (a.x + b.x) / 2.0,
+            (a.y + b.y) / 2.0,
+            vec2::distance([a.x, a.y], [b.x, b.y]),
+        ))
+    }
+
+    pub fn update(&mut self) {
+        self.touches
+            .retain(|_, touch| !matches!(touch.phase, TouchPhase::Ended | TouchPhase::Cancelled));
+        for touch in self.touches.values_mut() {
+            touch.phase = TouchPhase::Moved;
+            touch.delta_x = 0.0;
+            touch.delta_y = 0.0;
+        }
+
+        if self
+            .primary
+            .is_some_and(|id| !self.touches.contains_key(&id))
+        {
+            self.primary = None;
+        }
+
+        self.gestures.clear();
+    }
+}
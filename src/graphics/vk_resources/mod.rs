@@ -1,2 +1,3 @@
 pub mod buffer;
+pub mod pipeline;
 pub mod render_pass;
@@ -0,0 +1,342 @@
+use std::{cell, ffi::CStr, io, rc};
+
+use ash::{util::*, vk};
+
+use crate::graphics::{vk_base::*, *};
+
+// Each draw writes its own descriptor set, as rewriting one that's already bound in the frame's
+// command buffer would invalidate it. So a pipeline can be drawn more than once a frame, eg:
+// into a render target and to the screen, up to this many times.
+const MAX_DRAWS_PER_FRAME: u32 = 16;
+
+pub enum Blend {
+    Opaque,
+    Alpha,
+    Additive,
+}
+
+pub struct PipelineInfo<'a> {
+    pub vertex_spv: &'a [u8],
+    pub fragment_spv: &'a [u8],
+    pub descriptor_layout_bindings: &'a [vk::DescriptorSetLayoutBinding],
+    pub vertex_input_binding_descriptions: &'a [vk::VertexInputBindingDescription],
+    pub vertex_input_attribute_descriptions: &'a [vk::VertexInputAttributeDescription],
+    pub push_constant_ranges: &'a [vk::PushConstantRange],
+    pub blend: Blend,
+    // Translucent geometry that overlaps itself usually shouldn't hide what's drawn after it.
+    pub depth_write: bool,
+}
+
+pub struct Pipeline {
+    device_data: rc::Rc<device_data::DeviceData>,
+
+    vertex_shader_module: vk::ShaderModule,
+    fragment_shader_module: vk::ShaderModule,
+
+    pipelines: Vec<vk::Pipeline>,
+    pipeline_layout: vk::PipelineLayout,
+
+    descriptor_sets: Vec<vk::DescriptorSet>,
+    // The frame the descriptor sets were last handed out in, and how many were.
+    used_descriptor_sets: cell::Cell<(u64, usize)>,
+    descriptor_set_layouts: [vk::DescriptorSetLayout; 1],
+    descriptor_pool: vk::DescriptorPool,
+}
+
+impl Pipeline {
+    pub unsafe fn new(resources: &Resources, info: PipelineInfo) -> Self {
+        let descriptor_sizes: Vec<vk::DescriptorPoolSize> = info
+            .descriptor_layout_bindings
+            .iter()
+            .map(|binding| vk::DescriptorPoolSize {
+                ty: binding.descriptor_type,
+                descriptor_count: binding.descriptor_count * MAX_DRAWS_PER_FRAME,
+            })
+            .collect();
+        let descriptor_pool_info = vk::DescriptorPoolCreateInfo::builder()
+            .pool_sizes(&descriptor_sizes)
+            .max_sets(MAX_DRAWS_PER_FRAME)
+            .build();
+        let descriptor_pool = resources
+            .base
+            .device_data
+            .device
+            .create_descriptor_pool(&descriptor_pool_info, None)
+            .unwrap();
+        let descriptor_info = vk::DescriptorSetLayoutCreateInfo::builder()
+            .bindings(info.descriptor_layout_bindings)
+            .build();
+
+        let descriptor_set_layouts = [resources
+            .base
+            .device_data
+            .device
+            .create_descriptor_set_layout(&descriptor_info, None)
+            .unwrap()];
+
+        let set_layouts = [descriptor_set_layouts[0]; MAX_DRAWS_PER_FRAME as usize];
+        let descriptor_alloc_info = vk::DescriptorSetAllocateInfo::builder()
+            .descriptor_pool(descriptor_pool)
+            .set_layouts(&set_layouts)
+            .build();
+        let descriptor_sets = resources
+            .base
+            .device_data
+            .device
+            .allocate_descriptor_sets(&descriptor_alloc_info)
+            .unwrap();
+
+        let mut vertex_spv_file = io::Cursor::new(info.vertex_spv);
+        let mut frag_spv_file = io::Cursor::new(info.fragment_spv);
+
+        let vertex_code =
+            read_spv(&mut vertex_spv_file).expect("Failed to read vertex shader spv file");
+        let vertex_shader_info = vk::ShaderModuleCreateInfo::builder()
+            .code(&vertex_code)
+            .build();
+
+        let frag_code =
+            read_spv(&mut frag_spv_file).expect("Failed to read fragment shader spv file");
+        let frag_shader_info = vk::ShaderModuleCreateInfo::builder()
+            .code(&frag_code)
+            .build();
+
+        let vertex_shader_module = resources
+            .base
+            .device_data
+            .device
+            .create_shader_module(&vertex_shader_info, None)
+            .expect("Failed to create vertex shader module");
+
+        let fragment_shader_module = resources
+            .base
+            .device_data
+            .device
+            .create_shader_module(&frag_shader_info, None)
+            .expect("Failed to create fragment shader module");
+
+        let layout_create_info = vk::PipelineLayoutCreateInfo::builder()
+            .set_layouts(&descriptor_set_layouts)
+            .push_constant_ranges(info.push_constant_ranges)
+            .build();
+
+        let pipeline_layout = resources
+            .base
+            .device_data
+            .device
+            .create_pipeline_layout(&layout_create_info, None)
+            .unwrap();
+
+        let shader_entry_name = CStr::from_bytes_with_nul_unchecked(b"main\0");
+        let shader_stage_create_infos = [
+            vk::PipelineShaderStageCreateInfo {
+                module: vertex_shader_module,
+                p_name: shader_entry_name.as_ptr(),
+                stage: vk::ShaderStageFlags::VERTEX,
+                ..Default::default()
+            },
+            vk::PipelineShaderStageCreateInfo {
+                module: fragment_shader_module,
+                p_name: shader_entry_name.as_ptr(),
+                stage: vk::ShaderStageFlags::FRAGMENT,
+                ..Default::default()
+            },
+        ];
+
+        let viewport_state_info = vk::PipelineViewportStateCreateInfo::builder()
+            .scissors(&resources.scissors)
+            .viewports(&resources.viewports)
+            .build();
+
+        let rasterization_info = vk::PipelineRasterizationStateCreateInfo {
+            front_face: vk::FrontFace::COUNTER_CLOCKWISE,
+            line_width: 1.0,
+            polygon_mode: vk::PolygonMode::FILL,
+            ..Default::default()
+        };
+
+        let multisample_state_info = vk::PipelineMultisampleStateCreateInfo::builder()
+            .rasterization_samples(vk::SampleCountFlags::TYPE_1)
+            .build();
+
+        let noop_stencil_state = vk::StencilOpState {
+            fail_op: vk::StencilOp::KEEP,
+            pass_op: vk::StencilOp::KEEP,
+            depth_fail_op: vk::StencilOp::KEEP,
+            compare_op: vk::CompareOp::ALWAYS,
+            ..Default::default()
+        };
+        let depth_state_info = vk::PipelineDepthStencilStateCreateInfo {
+            depth_test_enable: 1,
+            depth_write_enable: info.depth_write as u32,
+            depth_compare_op: vk::CompareOp::LESS_OR_EQUAL,
+            front: noop_stencil_state,
+            back: noop_stencil_state,
+            max_depth_bounds: 1.0,
+            ..Default::default()
+        };
+
+        let color_blend_attachment_states = [match info.blend {
+            Blend::Opaque => vk::PipelineColorBlendAttachmentState {
+                blend_enable: 0,
+                src_color_blend_factor: vk::BlendFactor::SRC_COLOR,
+                dst_color_blend_factor: vk::BlendFactor::ONE_MINUS_DST_COLOR,
+                color_blend_op: vk::BlendOp::ADD,
+                src_alpha_blend_factor: vk::BlendFactor::ZERO,
+                dst_alpha_blend_factor: vk::BlendFactor::ZERO,
+                alpha_blend_op: vk::BlendOp::ADD,
+                color_write_mask: vk::ColorComponentFlags::RGBA,
+            },
+            Blend::Alpha => vk::PipelineColorBlendAttachmentState {
+                blend_enable: 1,
+                src_color_blend_factor: vk::BlendFactor::SRC_ALPHA,
+                dst_color_blend_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+                color_blend_op: vk::BlendOp::ADD,
+                src_alpha_blend_factor: vk::BlendFactor::ONE,
+                dst_alpha_blend_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+                alpha_blend_op: vk::BlendOp::ADD,
+                color_write_mask: vk::ColorComponentFlags::RGBA,
+            },
+            Blend::Additive => vk::PipelineColorBlendAttachmentState {
+                blend_enable: 1,
+                src_color_blend_factor: vk::BlendFactor::SRC_ALPHA,
+                dst_color_blend_factor: vk::BlendFactor::ONE,
+                color_blend_op: vk::BlendOp::ADD,
+                src_alpha_blend_factor: vk::BlendFactor::ONE,
+                dst_alpha_blend_factor: vk::BlendFactor::ONE,
+                alpha_blend_op: vk::BlendOp::ADD,
+                color_write_mask: vk::ColorComponentFlags::RGBA,
+            },
+        }];
+        let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
+            .logic_op(vk::LogicOp::CLEAR)
+            .attachments(&color_blend_attachment_states)
+            .build();
+
+        let dynamic_state = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+        let dynamic_state_info = vk::PipelineDynamicStateCreateInfo::builder()
+            .dynamic_states(&dynamic_state)
+            .build();
+
+        let vertex_input_state_info = vk::PipelineVertexInputStateCreateInfo::builder()
+            .vertex_attribute_descriptions(info.vertex_input_attribute_descriptions)
+            .vertex_binding_descriptions(info.vertex_input_binding_descriptions)
+            .build();
+
+        let vertex_input_assembly_state_info = vk::PipelineInputAssemblyStateCreateInfo {
+            topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+            ..Default::default()
+        };
+
+        let graphic_pipeline_infos = vk::GraphicsPipelineCreateInfo::builder()
+            .stages(&shader_stage_create_infos)
+            .vertex_input_state(&vertex_input_state_info)
+            .input_assembly_state(&vertex_input_assembly_state_info)
+            .viewport_state(&viewport_state_info)
+            .rasterization_state(&rasterization_info)
+            .multisample_state(&multisample_state_info)
+            .depth_stencil_state(&depth_state_info)
+            .color_blend_state(&color_blend_state)
+            .dynamic_state(&dynamic_state_info)
+            .layout(pipeline_layout)
+            .render_pass(resources.render_pass.vk_render_pass)
+            .build();
+
+        let pipelines = resources
+            .base
+            .device_data
+            .device
+            .create_graphics_pipelines(vk::PipelineCache::null(), &[graphic_pipeline_infos], None)
+            .unwrap();
+
+        Self {
+            device_data: resources.base.device_data.clone(),
+
+            vertex_shader_module,
+            fragment_shader_module,
+
+            pipelines,
+            pipeline_layout,
+
+            descriptor_sets,
+            used_descriptor_sets: cell::Cell::new((0, 0)),
+            descriptor_set_layouts,
+            descriptor_pool,
+        }
+    }
+
+    pub fn layout(&self) -> vk::PipelineLayout {
+        self.pipeline_layout
+    }
+
+    // A descriptor set that isn't used yet this frame, to write the draw's descriptors to and
+    // then bind. The previous frame's are reused, as it's finished by the time this one draws.
+    pub fn descriptor_set(&self, draw: &Draw) -> vk::DescriptorSet {
+        let (frame, used) = self.used_descriptor_sets.get();
+        let used = if frame == draw.resources.frame {
+            used
+        } else {
+            0
+        };
+
+        let descriptor_set = *self.descriptor_sets.get(used).unwrap_or_else(|| {
+            panic!(
+                "Pipelines can only be drawn {} times per frame",
+                MAX_DRAWS_PER_FRAME
+            )
+        });
+        self.used_descriptor_sets
+            .set((draw.resources.frame, used + 1));
+
+        descriptor_set
+    }
+
+    pub unsafe fn bind(&self, draw: &Draw, descriptor_set: vk::DescriptorSet) {
+        draw.device.cmd_bind_descriptor_sets(
+            draw.command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.pipeline_layout,
+            0,
+            &[descriptor_set],
+            &[],
+        );
+        draw.device.cmd_bind_pipeline(
+            draw.command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.pipelines[0],
+        );
+        draw.device
+            .cmd_set_viewport(draw.command_buffer, 0, &draw.viewports);
+        draw.device
+            .cmd_set_scissor(draw.command_buffer, 0, &draw.scissors);
+    }
+}
+
+impl Drop for Pipeline {
+    fn drop(&mut self) {
+        unsafe {
+            self.device_data.device.device_wait_idle().unwrap();
+
+            for pipeline in &self.pipelines {
+                self.device_data.device.destroy_pipeline(*pipeline, None);
+            }
+            self.device_data
+                .device
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+            self.device_data
+                .device
+                .destroy_shader_module(self.vertex_shader_module, None);
+            self.device_data
+                .device
+                .destroy_shader_module(self.fragment_shader_module, None);
+            for &descriptor_set_layout in self.descriptor_set_layouts.iter() {
+                self.device_data
+                    .device
+                    .destroy_descriptor_set_layout(descriptor_set_layout, None);
+            }
+            self.device_data
+                .device
+                .destroy_descriptor_pool(self.descriptor_pool, None);
+        }
+    }
+}
@@ -15,12 +15,20 @@ impl RenderPass {
         device_data: rc::Rc<device_data::DeviceData>,
         surface_data: &surface_data::SurfaceData,
         swapchain_data: &swapchain_data::SwapchainData,
-        window: &winit::window::Window,
     ) -> Self {
         let surface_format = surface_data
             .format
             .expect("Surface format was uninitialized when creating render pass");
 
+        let dependencies = [vk::SubpassDependency {
+            src_subpass: vk::SUBPASS_EXTERNAL,
+            src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            dst_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_READ
+                | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            dst_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            ..Default::default()
+        }];
+
         let renderpass_attachments = [
             vk::AttachmentDescription {
                 format: surface_format.format,
@@ -39,6 +47,117 @@ impl RenderPass {
                 ..Default::default()
             },
         ];
+
+        let vk_render_pass =
+            Self::new_vk_render_pass(&device_data, &renderpass_attachments, &dependencies);
+
+        let mut framebuffers = Vec::new();
+        Self::new_framebuffers(
+            &mut framebuffers,
+            surface_data,
+            vk_render_pass,
+            device_data.clone(),
+            swapchain_data,
+        );
+
+        Self {
+            device_data,
+            vk_render_pass,
+            framebuffers,
+        }
+    }
+
+    // Renders into an image that's sampled afterwards, rather than a swapchain image.
+    // It uses the same formats as the swapchain's render pass, so pipelines work with both.
+    // The color image keeps what was drawn before, while depth is cleared each time.
+    pub unsafe fn new_offscreen(
+        device_data: rc::Rc<device_data::DeviceData>,
+        surface_data: &surface_data::SurfaceData,
+        color_image_view: vk::ImageView,
+        depth_image_view: vk::ImageView,
+        extent: vk::Extent2D,
+    ) -> Self {
+        let surface_format = surface_data
+            .format
+            .expect("Surface format was uninitialized when creating render pass");
+
+        let dependencies = [
+            // Wait for earlier passes to finish sampling the image, and with the depth
+            // image, before drawing over them.
+            vk::SubpassDependency {
+                src_subpass: vk::SUBPASS_EXTERNAL,
+                src_stage_mask: vk::PipelineStageFlags::FRAGMENT_SHADER
+                    | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+                src_access_mask: vk::AccessFlags::SHADER_READ
+                    | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                dst_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                    | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
+                dst_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_READ
+                    | vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                    | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                ..Default::default()
+            },
+            vk::SubpassDependency {
+                src_subpass: 0,
+                dst_subpass: vk::SUBPASS_EXTERNAL,
+                src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                src_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                dst_stage_mask: vk::PipelineStageFlags::FRAGMENT_SHADER,
+                dst_access_mask: vk::AccessFlags::SHADER_READ,
+                ..Default::default()
+            },
+        ];
+
+        let renderpass_attachments = [
+            vk::AttachmentDescription {
+                format: surface_format.format,
+                samples: vk::SampleCountFlags::TYPE_1,
+                load_op: vk::AttachmentLoadOp::LOAD,
+                store_op: vk::AttachmentStoreOp::STORE,
+                initial_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                final_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                ..Default::default()
+            },
+            vk::AttachmentDescription {
+                format: vk::Format::D16_UNORM,
+                samples: vk::SampleCountFlags::TYPE_1,
+                load_op: vk::AttachmentLoadOp::CLEAR,
+                store_op: vk::AttachmentStoreOp::DONT_CARE,
+                initial_layout: vk::ImageLayout::UNDEFINED,
+                final_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+                ..Default::default()
+            },
+        ];
+
+        let vk_render_pass =
+            Self::new_vk_render_pass(&device_data, &renderpass_attachments, &dependencies);
+
+        let framebuffer_attachments = [color_image_view, depth_image_view];
+        let frame_buffer_create_info = vk::FramebufferCreateInfo::builder()
+            .render_pass(vk_render_pass)
+            .attachments(&framebuffer_attachments)
+            .width(extent.width)
+            .height(extent.height)
+            .layers(1)
+            .build();
+
+        let framebuffers = vec![device_data
+            .device
+            .create_framebuffer(&frame_buffer_create_info, None)
+            .unwrap()];
+
+        Self {
+            device_data,
+            vk_render_pass,
+            framebuffers,
+        }
+    }
+
+    unsafe fn new_vk_render_pass(
+        device_data: &device_data::DeviceData,
+        renderpass_attachments: &[vk::AttachmentDescription],
+        dependencies: &[vk::SubpassDependency],
+    ) -> vk::RenderPass {
         let color_attachment_refs = [vk::AttachmentReference {
             attachment: 0,
             layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
@@ -47,14 +166,6 @@ impl RenderPass {
             attachment: 1,
             layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
         };
-        let dependencies = [vk::SubpassDependency {
-            src_subpass: vk::SUBPASS_EXTERNAL,
-            src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
-            dst_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_READ
-                | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
-            dst_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
-            ..Default::default()
-        }];
 
         let subpass = vk::SubpassDescription::builder()
             .color_attachments(&color_attachment_refs)
@@ -63,40 +174,25 @@ impl RenderPass {
             .build();
 
         let renderpass_create_info = vk::RenderPassCreateInfo::builder()
-            .attachments(&renderpass_attachments)
+            .attachments(renderpass_attachments)
             .subpasses(std::slice::from_ref(&subpass))
-            .dependencies(&dependencies)
+            .dependencies(dependencies)
             .build();
 
-        let vk_render_pass = device_data
+        device_data
             .device
             .create_render_pass(&renderpass_create_info, None)
-            .unwrap();
-
-        let mut framebuffers = Vec::new();
-        Self::new_framebuffers(
-            &mut framebuffers,
-            window,
-            vk_render_pass,
-            device_data.clone(),
-            swapchain_data,
-        );
-
-        Self {
-            device_data,
-            vk_render_pass,
-            framebuffers,
-        }
+            .unwrap()
     }
 
     pub unsafe fn resize(
         &mut self,
-        window: &winit::window::Window,
+        surface_data: &surface_data::SurfaceData,
         swapchain_data: &swapchain_data::SwapchainData,
     ) {
         Self::new_framebuffers(
             &mut self.framebuffers,
-            window,
+            surface_data,
             self.vk_render_pass,
             self.device_data.clone(),
             swapchain_data,
@@ -107,14 +203,14 @@ impl RenderPass {
         &self,
         device: &ash::Device,
         command_buffer: vk::CommandBuffer,
-        surface_data: &surface_data::SurfaceData,
-        present_index: u32,
+        framebuffer_index: u32,
+        extent: vk::Extent2D,
         clear_values: &[vk::ClearValue],
     ) {
         let render_pass_begin_info = vk::RenderPassBeginInfo::builder()
             .render_pass(self.vk_render_pass)
-            .framebuffer(self.framebuffers[present_index as usize])
-            .render_area(surface_data.resolution.into())
+            .framebuffer(self.framebuffers[framebuffer_index as usize])
+            .render_area(extent.into())
             .clear_values(clear_values)
             .build();
 
@@ -131,7 +227,7 @@ impl RenderPass {
 
     unsafe fn new_framebuffers(
         framebuffers: &mut Vec<vk::Framebuffer>,
-        window: &winit::window::Window,
+        surface_data: &surface_data::SurfaceData,
         render_pass: vk::RenderPass,
         device_data: rc::Rc<device_data::DeviceData>,
         swapchain_data: &swapchain_data::SwapchainData,
@@ -142,15 +238,13 @@ impl RenderPass {
 
         framebuffers.clear();
 
-        let window_size = window.inner_size();
-
         for present_image_view in &swapchain_data.present_image_views {
             let framebuffer_attachments = [*present_image_view, swapchain_data.depth_image_view];
             let frame_buffer_create_info = vk::FramebufferCreateInfo::builder()
                 .render_pass(render_pass)
                 .attachments(&framebuffer_attachments)
-                .width(window_size.width)
-                .height(window_size.height)
+                .width(surface_data.resolution.width)
+                .height(surface_data.resolution.height)
                 .layers(1)
                 .build();
 
@@ -0,0 +1,367 @@
+use std::{fs, io, rc};
+
+pub use winit::window::CursorIcon;
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    window::{CursorGrabMode, Fullscreen, Icon, Window},
+};
+
+use super::{mat4, material, sprite_batch, texture, Draw, Resources};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CursorGrab {
+    None,
+    // Keeps the cursor inside the window.
+    Confined,
+    // Keeps the cursor in place, mouse deltas still report movement.
+    Locked,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindowMode {
+    Windowed,
+    // A window without decorations covering the monitor it's on.
+    BorderlessFullscreen,
+    // Switches the monitor to its largest video mode, falling back to borderless
+    // when that isn't supported.
+    Fullscreen,
+}
+
+// An image drawn in place of the cursor, with the hotspot being the pixel that points.
+#[derive(Clone)]
+pub struct CursorImage {
+    pub texture: rc::Rc<texture::Texture>,
+    pub hotspot_x: f32,
+    pub hotspot_y: f32,
+}
+
+// Changes to the window requested by the app, applied after each update.
+// The window's size and position are in physical pixels.
+pub struct WindowControl {
+    cursor_grab: CursorGrab,
+    cursor_visible: bool,
+    cursor_icon: CursorIcon,
+    cursor_image: Option<CursorImage>,
+    exit_requested: bool,
+    ime_allowed: bool,
+    ime_cursor_area: Option<[f32; 4]>,
+    title: String,
+    mode: WindowMode,
+
+    pending_size: Option<(u32, u32)>,
+    pending_position: Option<(i32, i32)>,
+    pending_icon: Option<Icon>,
+
+    applied_cursor_grab: CursorGrab,
+    applied_cursor_visible: bool,
+    applied_cursor_icon: CursorIcon,
+    applied_ime_allowed: bool,
+    applied_ime_cursor_area: Option<[f32; 4]>,
+    applied_title: String,
+    applied_mode: WindowMode,
+
+    // The window's state as of the start of the frame.
+    size: (u32, u32),
+    position: Option<(i32, i32)>,
+    scale_factor: f32,
+}
+
+impl WindowControl {
+    pub(super) fn new(window: &Window, title: &str) -> Self {
+        let mut window_control = Self {
+            cursor_grab: CursorGrab::None,
+            cursor_visible: true,
+            cursor_icon: CursorIcon::Default,
+            cursor_image: None,
+            exit_requested: false,
+            ime_allowed: false,
+            ime_cursor_area: None,
+            title: title.to_string(),
+            mode: WindowMode::Windowed,
+
+            pending_size: None,
+            pending_position: None,
+            pending_icon: None,
+
+            applied_cursor_grab: CursorGrab::None,
+            applied_cursor_visible: true,
+            applied_cursor_icon: CursorIcon::Default,
+            applied_ime_allowed: false,
+            applied_ime_cursor_area: None,
+            applied_title: title.to_string(),
+            applied_mode: WindowMode::Windowed,
+
+            size: (0, 0),
+            position: None,
+            scale_factor: 1.0,
+        };
+        window_control.refresh(window);
+
+        window_control
+    }
+
+    pub fn cursor_grab(&self) -> CursorGrab {
+        self.cursor_grab
+    }
+
+    pub fn set_cursor_grab(&mut self, grab: CursorGrab) {
+        self.cursor_grab = grab;
+    }
+
+    pub fn is_cursor_visible(&self) -> bool {
+        self.cursor_visible
+    }
+
+    pub fn set_cursor_visible(&mut self, visible: bool) {
+        self.cursor_visible = visible;
+    }
+
+    pub fn cursor_icon(&self) -> CursorIcon {
+        self.cursor_icon
+    }
+
+    // One of the system's cursors, shown while there's no cursor image.
+    pub fn set_cursor_icon(&mut self, icon: CursorIcon) {
+        self.cursor_icon = icon;
+    }
+
+    pub fn cursor_image(&self) -> Option<&CursorImage> {
+        self.cursor_image.as_ref()
+    }
+
+    // Hides the system cursor and draws the image at the mouse position over
+    // everything the app draws. It's drawn with each frame, so it can trail slightly.
+    pub fn set_cursor_image(&mut self, image: Option<CursorImage>) {
+        self.cursor_image = image;
+    }
+
+    pub fn is_ime_allowed(&self) -> bool {
+        self.ime_allowed
+    }
+
+    // Allow while a text field is focused, otherwise some keys are swallowed by the
+    // input method instead of reaching the game.
+    pub fn set_ime_allowed(&mut self, allowed: bool) {
+        self.ime_allowed = allowed;
+    }
+
+    // The text cursor's rect in the same pixels as the mouse, so the input method's candidate box
+    // can be placed next to it rather than over it.
+    pub fn set_ime_cursor_area(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        self.ime_cursor_area = Some([x, y, width, height]);
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        self.title = title.to_string();
+    }
+
+    pub fn mode(&self) -> WindowMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: WindowMode) {
+        self.mode = mode;
+    }
+
+    // The size of the window's contents, without its decorations.
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    pub fn set_size(&mut self, width: u32, height: u32) {
+        self.pending_size = Some((width, height));
+    }
+
+    // The position of the window's top left corner on the desktop, None on
+    // platforms where windows can't tell, eg: Wayland.
+    pub fn position(&self) -> Option<(i32, i32)> {
+        self.position
+    }
+
+    pub fn set_position(&mut self, x: i32, y: i32) {
+        self.pending_position = Some((x, y));
+    }
+
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
+    // Loads the icon the same way as Texture::new, some platforms ignore it.
+    pub fn set_icon(&mut self, path: &str) {
+        let file = fs::File::open(path).unwrap_or_else(|_| panic!("Failed to load file {}", path));
+
+        let image = image::load(io::BufReader::new(file), image::ImageFormat::Png)
+            .unwrap()
+            .to_rgba8();
+        let (width, height) = image.dimensions();
+
+        self.pending_icon =
+            Some(Icon::from_rgba(image.into_raw(), width, height).expect("Failed to create icon"));
+    }
+
+    // Closes the window and returns from Graphics::run, skipping the current frame's draw.
+    pub fn exit(&mut self) {
+        self.exit_requested = true;
+    }
+
+    pub fn is_exit_requested(&self) -> bool {
+        self.exit_requested
+    }
+
+    pub(super) fn refresh(&mut self, window: &Window) {
+        let size = window.inner_size();
+        self.size = (size.width, size.height);
+        self.position = window
+            .outer_position()
+            .ok()
+            .map(|position| (position.x, position.y));
+        self.scale_factor = window.scale_factor() as f32;
+    }
+
+    // The offset and scale take the pixels the mouse is measured in to physical pixels.
+    pub(super) fn apply(&mut self, window: &Window, screen_offset: [f32; 2], screen_scale: f32) {
+        if self.cursor_grab != self.applied_cursor_grab {
+            self.applied_cursor_grab = self.cursor_grab;
+
+            // Platforms only support some grab modes, so fall back to the other one.
+            let result = match self.cursor_grab {
+                CursorGrab::None => window.set_cursor_grab(CursorGrabMode::None),
+                CursorGrab::Confined => window
+                    .set_cursor_grab(CursorGrabMode::Confined)
+                    .or_else(|_| window.set_cursor_grab(CursorGrabMode::Locked)),
+                CursorGrab::Locked => window
+                    .set_cursor_grab(CursorGrabMode::Locked)
+                    .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined)),
+            };
+            if let Err(error) = result {
+                eprintln!("Failed to set cursor grab: {}", error);
+            }
+        }
+
+        // Cursor images are drawn instead of the system cursor.
+        let system_cursor_visible = self.cursor_visible && self.cursor_image.is_none();
+        if system_cursor_visible != self.applied_cursor_visible {
+            self.applied_cursor_visible = system_cursor_visible;
+            window.set_cursor_visible(system_cursor_visible);
+        }
+
+        if self.cursor_icon != self.applied_cursor_icon {
+            self.applied_cursor_icon = self.cursor_icon;
+            window.set_cursor_icon(self.cursor_icon);
+        }
+
+        if self.ime_allowed != self.applied_ime_allowed {
+            self.applied_ime_allowed = self.ime_allowed;
+            window.set_ime_allowed(self.ime_allowed);
+        }
+
+        if self.ime_cursor_area != self.applied_ime_cursor_area {
+            self.applied_ime_cursor_area = self.ime_cursor_area;
+
+            // Winit only takes a position, the candidate box goes below the cursor.
+            if let Some([x, y, _, height]) = self.ime_cursor_area {
+                window.set_ime_position(PhysicalPosition::new(
+                    x * screen_scale + screen_offset[0],
+                    (y + height) * screen_scale + screen_offset[1],
+                ));
+            }
+        }
+
+        if self.title != self.applied_title {
+            self.applied_title = self.title.clone();
+            window.set_title(&self.title);
+        }
+
+        if self.mode != self.applied_mode {
+            self.applied_mode = self.mode;
+
+            let fullscreen = match self.mode {
+                WindowMode::Windowed => None,
+                WindowMode::BorderlessFullscreen => Some(Fullscreen::Borderless(None)),
+                WindowMode::Fullscreen => Some(
+                    window
+                        .current_monitor()
+                        .and_then(|monitor| {
+                            monitor.video_modes().max_by_key(|video_mode| {
+                                let size = video_mode.size();
+                                (
+                                    size.width * size.height,
+                                    video_mode.refresh_rate_millihertz(),
+                                )
+                            })
+                        })
+                        .map(Fullscreen::Exclusive)
+                        .unwrap_or(Fullscreen::Borderless(None)),
+                ),
+            };
+            window.set_fullscreen(fullscreen);
+        }
+
+        if let Some((width, height)) = self.pending_size.take() {
+            window.set_inner_size(PhysicalSize::new(width, height));
+        }
+
+        if let Some((x, y)) = self.pending_position.take() {
+            window.set_outer_position(PhysicalPosition::new(x, y));
+        }
+
+        if let Some(icon) = self.pending_icon.take() {
+            window.set_window_icon(Some(icon));
+        }
+    }
+}
+
+// Draws the cursor image over everything else.
+pub(super) struct CursorSprite {
+    texture: rc::Rc<texture::Texture>,
+    sprite_batch: sprite_batch::SpriteBatch,
+    position: [f32; 2],
+}
+
+impl CursorSprite {
+    pub fn new(resources: &Resources, image: &CursorImage, x: f32, y: f32) -> Self {
+        let mut cursor_sprite = Self {
+            texture: image.texture.clone(),
+            sprite_batch: sprite_batch::SpriteBatch::with_material(
+                resources,
+                image.texture.clone(),
+                material::Material::translucent_sprite(),
+            ),
+            position: [x, y],
+        };
+        cursor_sprite.batch(image);
+
+        cursor_sprite
+    }
+
+    pub fn is_for(&self, image: &CursorImage) -> bool {
+        rc::Rc::ptr_eq(&self.texture, &image.texture)
+    }
+
+    // Only rebuilds the batch when the cursor moved.
+    pub fn update(&mut self, image: &CursorImage, x: f32, y: f32) {
+        if self.position != [x, y] {
+            self.position = [x, y];
+            self.batch(image);
+        }
+    }
+
+    fn batch(&mut self, image: &CursorImage) {
+        self.sprite_batch.batch(&[sprite_batch::Sprite {
+            x: self.position[0] - image.hotspot_x,
+            y: self.position[1] - image.hotspot_y,
+            z: mat4::Z_VIEW_DISTANCE,
+            width: self.texture.width() as f32,
+            height: self.texture.height() as f32,
+            region: sprite_batch::Region::FULL,
+        }]);
+    }
+
+    pub fn draw(&self, draw: &Draw) {
+        self.sprite_batch.draw(draw);
+    }
+}
@@ -1,8 +1,8 @@
 mod graphics;
 
-use graphics::{texture, *};
+use graphics::{color::Color, shape_batch, texture, *};
 
-use winit::event::VirtualKeyCode;
+use winit::event::{MouseButton, VirtualKeyCode};
 
 use std::rc;
 
@@ -12,7 +12,13 @@ struct App {
     time: f32,
     sprite_batch: sprite_batch::SpriteBatch,
     evil_sprite_batch: sprite_batch::SpriteBatch,
+    shape_batch: shape_batch::ShapeBatch,
     player_y: f32,
+    drag_start_player_y: f32,
+    actions: ActionMap,
+    preview_target: render_target::RenderTarget,
+    preview_contents_batch: sprite_batch::SpriteBatch,
+    preview_batch: sprite_batch::SpriteBatch,
 }
 
 impl app::App for App {
@@ -28,31 +34,161 @@ impl app::App for App {
             texture::Filter::Nearest,
         ));
 
+        // A small preview of the rust sprite, drawn into a render target and shown in a corner.
+        let preview_target =
+            render_target::RenderTarget::new(resources, 64, 64, texture::Filter::Nearest, false);
+        let mut preview_contents_batch =
+            sprite_batch::SpriteBatch::new(resources, rust_texture.clone());
+        preview_contents_batch.batch(&[sprite_batch::Sprite {
+            x: 0.0,
+            y: 16.0,
+            z: 0.0,
+            width: 64.0,
+            height: 32.0,
+            region: sprite_batch::Region::FULL,
+        }]);
+        let mut preview_batch = sprite_batch::SpriteBatch::new(resources, preview_target.texture());
+        preview_batch.batch(&[sprite_batch::Sprite {
+            x: 8.0,
+            y: 8.0,
+            z: 3.0,
+            width: 64.0,
+            height: 64.0,
+            region: sprite_batch::Region::FULL,
+        }]);
+
+        // The evil sprites dissolve away and back, with an orange edge. With the shader
+        // compiler, editing shader/dissolve.frag updates them while the game runs.
+        #[cfg(feature = "shader-compiler")]
+        let dissolve_shader = material::Shader {
+            fragment_spv: resources.shader_watcher().load("shader/dissolve.frag"),
+            ..material::Shader::sprite()
+        };
+        #[cfg(not(feature = "shader-compiler"))]
+        let dissolve_shader =
+            material::Shader::from_fragment(include_bytes!("../shader/dissolve.frag.spv"));
+        let mut dissolve = material::Material::new(rc::Rc::new(dissolve_shader));
+        dissolve.params[1] = Color::rgb(1.0, 0.5, 0.0).to_array();
+
+        let mut actions = ActionMap::new();
+        actions.bind(
+            "move_y",
+            ActionBinding::Composite {
+                negative: InputButton::Key(VirtualKeyCode::Up),
+                positive: InputButton::Key(VirtualKeyCode::Down),
+            },
+        );
+        actions.bind(
+            "move_y",
+            ActionBinding::Composite {
+                negative: InputButton::PhysicalKey(PhysicalKey::W),
+                positive: InputButton::PhysicalKey(PhysicalKey::S),
+            },
+        );
+        actions.bind(
+            "move_y",
+            ActionBinding::Composite {
+                negative: InputButton::Gamepad(GamepadButton::DPadUp),
+                positive: InputButton::Gamepad(GamepadButton::DPadDown),
+            },
+        );
+        // Sticks point up with positive values, unlike the y axis on screen.
+        actions.bind(
+            "move_y",
+            ActionBinding::Axis {
+                axis: InputAxis::Gamepad(GamepadAxis::LeftStickY),
+                scale: -1.0,
+            },
+        );
+
         Self {
             time: 0.0,
             sprite_batch: sprite_batch::SpriteBatch::new(resources, rust_texture),
-            evil_sprite_batch: sprite_batch::SpriteBatch::new(resources, evil_rust_texture),
+            evil_sprite_batch: sprite_batch::SpriteBatch::with_material(
+                resources,
+                evil_rust_texture,
+                dissolve,
+            ),
+            shape_batch: shape_batch::ShapeBatch::new(resources),
             player_y: 0.0,
+            drag_start_player_y: 0.0,
+            actions,
+            preview_target,
+            preview_contents_batch,
+            preview_batch,
         }
     }
 
     fn update(&mut self, resources: &mut Resources, input: &mut Input, delta_time: f32) {
         self.time += delta_time;
 
-        let mut player_direction = 0.0;
-
-        if input.is_key_held(VirtualKeyCode::Up) {
-            player_direction -= 1.0;
+        if input.was_key_pressed(VirtualKeyCode::Escape) {
+            resources.window().exit();
         }
-
-        if input.is_key_held(VirtualKeyCode::Down) {
-            player_direction += 1.0;
+        if input.was_key_pressed(VirtualKeyCode::F11) {
+            let mode = match resources.window().mode() {
+                window::WindowMode::Windowed => window::WindowMode::BorderlessFullscreen,
+                _ => window::WindowMode::Windowed,
+            };
+            resources.window().set_mode(mode);
+        }
+        if input.was_key_pressed(VirtualKeyCode::F9) {
+            let effects = if resources.post_effects().is_empty() {
+                vec![
+                    post_process::Effect::Bloom {
+                        threshold: 0.7,
+                        intensity: 1.0,
+                        radius: 1.0,
+                    },
+                    post_process::Effect::Crt {
+                        curvature: 0.05,
+                        scanline_intensity: 0.3,
+                    },
+                    post_process::Effect::Vignette {
+                        intensity: 0.6,
+                        radius: 0.5,
+                        softness: 0.5,
+                    },
+                ]
+            } else {
+                Vec::new()
+            };
+            resources.set_post_effects(effects);
+        }
+        if input.was_key_pressed(VirtualKeyCode::F10) {
+            match resources.virtual_resolution() {
+                Some(_) => resources.clear_virtual_resolution(),
+                None => {
+                    resources.set_virtual_resolution(320, 240, virtual_resolution::Scaling::Integer)
+                }
+            }
         }
 
+        let player_direction = self.actions.value(input, "move_y");
         self.player_y += player_direction * delta_time * PLAYER_SPEED;
+        if let Some(drag) = input.mouse_drag(MouseButton::Left) {
+            if drag.phase == DragPhase::Started {
+                self.drag_start_player_y = self.player_y;
+            }
+            self.player_y = self.drag_start_player_y + drag.delta_y;
+        }
+        if input.was_double_clicked(MouseButton::Left) {
+            self.player_y = 0.0;
+        }
+        for gesture in input.gestures() {
+            if let Gesture::Drag { delta_y, .. } = gesture {
+                self.player_y += delta_y;
+            }
+        }
 
         let sprite_position = self.time.sin() * 320.0 + 320.0;
 
+        #[cfg(feature = "shader-compiler")]
+        self.evil_sprite_batch.reload_shaders(resources);
+        let mut dissolve_params = self.evil_sprite_batch.material().params;
+        dissolve_params[0][0] = (self.time * 0.5).sin() * 0.5 + 0.5;
+        self.evil_sprite_batch.set_params(dissolve_params);
+
         self.evil_sprite_batch.batch(&[
             sprite_batch::Sprite {
                 x: 0.0,
@@ -60,6 +196,7 @@ impl app::App for App {
                 z: 1.0,
                 width: 64.0,
                 height: 32.0,
+                region: sprite_batch::Region::FULL,
             },
             sprite_batch::Sprite {
                 x: 16.0,
@@ -67,9 +204,34 @@ impl app::App for App {
                 z: -1.0,
                 width: 128.0,
                 height: 64.0,
+                region: sprite_batch::Region::FULL,
             },
         ]);
 
+        self.shape_batch.clear();
+        self.shape_batch.stroke_rect(
+            &shape_batch::Rect {
+                x: sprite_position,
+                y: self.player_y,
+                z: 2.0,
+                width: 64.0,
+                height: 32.0,
+            },
+            2.0,
+            Color::GREEN,
+        );
+        self.shape_batch.fill_ellipse(
+            &shape_batch::Ellipse::circle(
+                sprite_position + 32.0,
+                self.player_y - 16.0,
+                2.0,
+                8.0,
+                16,
+            ),
+            Color::RED,
+        );
+        self.shape_batch.upload();
+
         if self.time as usize % 2 == 0 {
             self.sprite_batch.batch(&[
                 sprite_batch::Sprite {
@@ -78,6 +240,7 @@ impl app::App for App {
                     z: 1.0,
                     width: 64.0,
                     height: 32.0,
+                    region: sprite_batch::Region::FULL,
                 },
                 sprite_batch::Sprite {
                     x: sprite_position + 16.0,
@@ -85,6 +248,7 @@ impl app::App for App {
                     z: -1.0,
                     width: 128.0,
                     height: 64.0,
+                    region: sprite_batch::Region::FULL,
                 },
             ]);
         } else {
@@ -92,15 +256,34 @@ impl app::App for App {
         }
     }
 
+    fn draw_offscreen(&mut self, offscreen: &Offscreen) {
+        offscreen.render(&self.preview_target, Some(Color::BLUE), |draw| {
+            self.preview_contents_batch.draw(draw);
+        });
+    }
+
     fn draw(&mut self, draw: &Draw) {
+        self.preview_batch.draw(draw);
         self.sprite_batch.draw(draw);
         self.evil_sprite_batch.draw(draw);
+        self.shape_batch.draw(draw);
     }
 }
 
 fn main() {
     unsafe {
         let mut graphics = Graphics::new("GPU VK");
+
+        // eg: `--record session.jsonl`, then `--replay session.jsonl` to play it back.
+        let args: Vec<String> = std::env::args().collect();
+        for pair in args.windows(2) {
+            match pair[0].as_str() {
+                "--record" => graphics.record(&pair[1]),
+                "--replay" => graphics.replay(&pair[1]),
+                _ => {}
+            }
+        }
+
         graphics.run::<App>();
     }
 }